@@ -1,44 +1,148 @@
+use std::error::Error;
 use std::fmt::{Display, Formatter};
 use std::net::SocketAddr;
+use async_trait::async_trait;
 use crate::Node;
+use crate::kv::{Entry, MerkleTree, Versioned};
 use mockall::automock;
 
 #[automock]
-pub trait Client {
+#[async_trait]
+pub trait Client: Send + Sync {
 
     /// Init the client
     ///
     /// # Arguments
     ///
     /// * `addr` - The node address to connect to
-    fn init(addr: SocketAddr) -> Self;
+    /// * `id` - The id of the node to talk to. A host running several virtual nodes serves all
+    ///   of them on the same address, so the id tells which of them the requests are for.
+    fn init(addr: SocketAddr, id: u64) -> Self;
 
     /// Find a successor of a given id.
     ///
     /// # Arguments
     ///
     /// * `id` - The id to find the successor for
-    fn find_successor(&self, id: u64) -> Result<Node, ClientError>;
+    async fn find_successor(&self, id: u64) -> Result<Node, ClientError>;
+
+    /// Get the closest preceding node of a given id from the node's finger table.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id to find the closest preceding node for
+    async fn closest_preceding_node(&self, id: u64) -> Result<Node, ClientError>;
 
     /// Get the successor of the node
-    fn successor(&self) -> Result<Node, ClientError>;
+    async fn successor(&self) -> Result<Node, ClientError>;
+
+    /// Get the successor list of the node
+    async fn successor_list(&self) -> Result<Vec<Node>, ClientError>;
 
     /// Get the predecessor of the node
-    fn predecessor(&self) -> Result<Option<Node>, ClientError>;
+    async fn predecessor(&self) -> Result<Option<Node>, ClientError>;
 
     /// Notify the node about a new predecessor
     ///
     /// # Arguments
     ///
     /// * `predecessor` - The new predecessor
-    fn notify(&self, predecessor: Node) -> Result<(), ClientError>;
+    async fn notify(&self, predecessor: Node) -> Result<(), ClientError>;
+
+    /// Notify the node that its successor is leaving the ring
+    ///
+    /// # Arguments
+    ///
+    /// * `leaving` - The node which is leaving the ring
+    /// * `successor` - The successor of the leaving node
+    async fn successor_leaving(&self, leaving: Node, successor: Node) -> Result<(), ClientError>;
+
+    /// Notify the node that its predecessor is leaving the ring
+    ///
+    /// # Arguments
+    ///
+    /// * `leaving` - The node which is leaving the ring
+    /// * `predecessor` - The predecessor of the leaving node, if it has one
+    async fn predecessor_leaving(&self, leaving: Node, predecessor: Option<Node>) -> Result<(), ClientError>;
 
     /// Ping the node
-    fn ping(&self) -> Result<(), ClientError>;
+    async fn ping(&self) -> Result<(), ClientError>;
+
+    /// Store the version of the key on the node
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to store the value for
+    /// * `version` - The value with its vector clock
+    async fn put(&self, key: Vec<u8>, version: Versioned) -> Result<(), ClientError>;
+
+    /// Get all the versions of the key stored on the node
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to get the versions for
+    async fn get(&self, key: Vec<u8>) -> Result<Vec<Versioned>, ClientError>;
+
+    /// Get the next batch of the keys stored on the node whose ids are in the range `(from, to]`
+    ///
+    /// An empty batch means that all the keys of the range were transferred.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The exclusive start of the range
+    /// * `to` - The inclusive end of the range
+    /// * `after` - The last key of the previous batch, `None` for the first batch
+    async fn transfer_keys(&self, from: u64, to: u64, after: Option<Vec<u8>>) -> Result<Vec<Entry>, ClientError>;
+
+    /// Get the next page of the keys stored on the node whose ids are in the range `(from, to]`,
+    /// in the order of the ring
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The exclusive start of the range
+    /// * `to` - The inclusive end of the range
+    /// * `after` - The last key of the previous page, `None` for the first page
+    /// * `limit` - The maximum number of keys to return
+    async fn scan(&self, from: u64, to: u64, after: Option<Vec<u8>>, limit: usize) -> Result<Vec<Entry>, ClientError>;
+
+    /// Confirm that the keys were transferred, so the node can drop the transferred versions
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - The transferred keys with their versions
+    async fn confirm_transfer(&self, entries: Vec<Entry>) -> Result<(), ClientError>;
+
+    /// Store copies of the keys the node is a replica of
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - The keys with their versions
+    async fn replicate(&self, entries: Vec<Entry>) -> Result<(), ClientError>;
+
+    /// Get the Merkle tree of the keys stored on the node whose ids are in the range `(from, to]`
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The exclusive start of the range
+    /// * `to` - The inclusive end of the range
+    async fn merkle_tree(&self, from: u64, to: u64) -> Result<MerkleTree, ClientError>;
 }
 
+/// Error returned by a [`Client`] request
+#[derive(Debug)]
 pub enum ClientError {
+    /// The node couldn't be reached
     ConnectionFailed(Node),
+    /// The node didn't respond in time
+    Timeout(Node),
+    /// The node received the request but refused to handle it, e.g. because it hasn't joined a
+    /// ring yet
+    Rejected(String),
+    /// The node speaks a different version of the protocol
+    ProtocolMismatch(String),
+    /// The transport failed, the underlying error is available through
+    /// [`source`](std::error::Error::source)
+    Transport(Box<dyn Error + Send + Sync>),
     Unexpected(String),
 }
 
@@ -46,7 +150,20 @@ impl Display for ClientError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             ClientError::ConnectionFailed(node) => write!(f, "Connection to node {} failed", node.addr()),
+            ClientError::Timeout(node) => write!(f, "Request to node {} timed out", node.addr()),
+            ClientError::Rejected(reason) => write!(f, "Request rejected: {}", reason),
+            ClientError::ProtocolMismatch(message) => write!(f, "Protocol mismatch: {}", message),
+            ClientError::Transport(err) => write!(f, "Transport error: {}", err),
             ClientError::Unexpected(message) => write!(f, "{}", message),
         }
     }
 }
+
+impl Error for ClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ClientError::Transport(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
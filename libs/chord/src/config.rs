@@ -0,0 +1,301 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::hasher::{IdHasher, SeaHasher};
+
+/// Default number of successors kept in the successor list
+pub const DEFAULT_SUCCESSOR_LIST_SIZE: usize = 3;
+
+/// Default number of nodes storing a copy of every key
+pub const DEFAULT_REPLICATION_FACTOR: usize = 1;
+
+/// Default maximum number of hints a node keeps for unreachable replicas
+pub const DEFAULT_HINT_CAPACITY: usize = 1024;
+
+/// Default time after which an undelivered hint is dropped
+pub const DEFAULT_HINT_TTL: Duration = Duration::from_secs(600);
+
+/// Default time after which the tombstone of a deleted key is dropped
+pub const DEFAULT_TOMBSTONE_TTL: Duration = Duration::from_secs(86400);
+
+/// Default number of bits of the identifier space
+pub const DEFAULT_RING_BITS: u8 = 64;
+
+/// Configuration shared by all the nodes of a ring
+///
+/// All the nodes of a ring have to use the same configuration, otherwise they would place the
+/// same node or key on different positions of the ring.
+#[derive(Clone, Debug)]
+pub struct RingConfig {
+    /// The number of bits of the identifiers (m).
+    ///
+    /// Identifiers are in the range `[0, 2^m)` and every node keeps m fingers. It has to be in the
+    /// range `1..=64`, see [`Config::validate`].
+    pub bits: u8,
+
+    /// The strategy used to derive ids from addresses and keys
+    pub hasher: Arc<dyn IdHasher>,
+}
+
+impl RingConfig {
+    /// Create a new ring configuration using the default [`SeaHasher`]
+    ///
+    /// # Arguments
+    ///
+    /// * `bits` - The number of bits of the identifiers, in the range `1..=64`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is not in the range `1..=64`.
+    pub fn new(bits: u8) -> Self {
+        Self::with_hasher(bits, SeaHasher)
+    }
+
+    /// Create a new ring configuration with the given id hasher
+    ///
+    /// # Arguments
+    ///
+    /// * `bits` - The number of bits of the identifiers, in the range `1..=64`
+    /// * `hasher` - The strategy used to derive ids from addresses and keys
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is not in the range `1..=64`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chord_rs::{RingConfig, Sha1Hasher};
+    ///
+    /// let ring = RingConfig::with_hasher(6, Sha1Hasher);
+    ///
+    /// // SHA-1("abc") = a9993e364706816aba3e25717850c26c9cd0d89d, 0x9d mod 2^6 = 29
+    /// assert_eq!(ring.id(b"abc"), 29);
+    /// ```
+    pub fn with_hasher(bits: u8, hasher: impl IdHasher + 'static) -> Self {
+        assert!((1..=64).contains(&bits), "The number of bits of the ring must be in range 1..=64, got {}", bits);
+        Self { bits, hasher: Arc::new(hasher) }
+    }
+
+    /// Mask the given id to the identifier space of the ring
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chord_rs::RingConfig;
+    ///
+    /// let ring = RingConfig::new(6);
+    ///
+    /// assert_eq!(ring.mask(70), 6);
+    /// assert_eq!(RingConfig::default().mask(u64::MAX), u64::MAX);
+    /// ```
+    pub fn mask(&self, id: u64) -> u64 {
+        if self.bits >= 64 {
+            id
+        } else {
+            id & ((1 << self.bits) - 1)
+        }
+    }
+
+    /// Derive an id on the ring from the given bytes
+    ///
+    /// The bytes are hashed with the configured hasher and the hash is masked to the identifier
+    /// space of the ring.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The bytes to hash, e.g. an address of a node or a key
+    pub fn id(&self, bytes: &[u8]) -> u64 {
+        self.mask(self.hasher.hash(bytes))
+    }
+}
+
+impl Default for RingConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_RING_BITS)
+    }
+}
+
+/// The way the finger table is refreshed by `NodeService::fix_fingers`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FixFingersMode {
+    /// Every call refreshes the whole finger table.
+    Full,
+    /// Every call refreshes the given number of fingers, starting where the previous call stopped.
+    ///
+    /// This is the mode described in the Chord paper. It spreads the lookups over time instead
+    /// of sending a burst of them on every call.
+    Incremental(usize),
+}
+
+/// Configuration of a node in the chord ring
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The number of successors the node keeps track of.
+    ///
+    /// When the immediate successor fails, the node falls back to the next live entry of the list.
+    pub successor_list_size: usize,
+
+    /// The way the finger table is refreshed
+    pub fix_fingers_mode: FixFingersMode,
+
+    /// The number of nodes storing a copy of every key of the [`KvStore`](crate::KvStore).
+    ///
+    /// The key is stored on its successor and replicated to the following nodes of the successor
+    /// list, each on a different physical host. So there are at most `successor_list_size + 1`
+    /// copies of a key.
+    pub replication_factor: usize,
+
+    /// The maximum number of hints the node keeps for unreachable replicas.
+    ///
+    /// A write to an unreachable replica is stored as a hint on the node coordinating the write
+    /// and replayed once the replica is back. When the queue is full, the write to the replica
+    /// fails.
+    pub hint_capacity: usize,
+
+    /// The time after which an undelivered hint is dropped
+    pub hint_ttl: Duration,
+
+    /// Whether a hint counts as an acknowledgement of the write to the unreachable replica.
+    ///
+    /// With a sloppy quorum a write succeeds as long as the hints could be stored, even though
+    /// fewer replicas than required have the value. It's disabled by default, so the write fails
+    /// and the hint is only kept to repair the replica.
+    pub sloppy_quorum: bool,
+
+    /// The time after which the tombstone of a deleted key is dropped.
+    ///
+    /// A replica which missed the delete brings the deleted value back once the tombstone is
+    /// dropped, so it has to be longer than the `hint_ttl` and than the time a replica is
+    /// expected to be down.
+    pub tombstone_ttl: Duration,
+
+    /// The configuration of the ring the node is part of
+    pub ring: RingConfig,
+}
+
+impl Config {
+    /// Check that a node can run with the configuration
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chord_rs::{Config, ConfigError, FixFingersMode};
+    ///
+    /// assert!(Config::default().validate().is_ok());
+    ///
+    /// let config = Config { successor_list_size: 0, ..Config::default() };
+    /// assert!(matches!(config.validate(), Err(ConfigError::EmptySuccessorList)));
+    ///
+    /// let config = Config { fix_fingers_mode: FixFingersMode::Incremental(0), ..Config::default() };
+    /// assert!(matches!(config.validate(), Err(ConfigError::NoFingersRefreshed)));
+    /// ```
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !(1..=64).contains(&self.ring.bits) {
+            return Err(ConfigError::InvalidRingBits(self.ring.bits));
+        }
+        if self.successor_list_size == 0 {
+            return Err(ConfigError::EmptySuccessorList);
+        }
+        if self.fix_fingers_mode == FixFingersMode::Incremental(0) {
+            return Err(ConfigError::NoFingersRefreshed);
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            successor_list_size: DEFAULT_SUCCESSOR_LIST_SIZE,
+            fix_fingers_mode: FixFingersMode::Full,
+            replication_factor: DEFAULT_REPLICATION_FACTOR,
+            hint_capacity: DEFAULT_HINT_CAPACITY,
+            hint_ttl: DEFAULT_HINT_TTL,
+            sloppy_quorum: false,
+            tombstone_ttl: DEFAULT_TOMBSTONE_TTL,
+            ring: RingConfig::default(),
+        }
+    }
+}
+
+/// Error returned by [`Config::validate`]
+#[derive(Debug, PartialEq)]
+pub enum ConfigError {
+    /// The successor list size is 0, so the node would have no successor
+    EmptySuccessorList,
+    /// The incremental refresh of the finger table refreshes no finger
+    NoFingersRefreshed,
+    /// The number of bits of the ring is not in the range `1..=64`
+    InvalidRingBits(u8),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptySuccessorList => write!(f, "The successor list size must be at least 1"),
+            Self::NoFingersRefreshed => write!(f, "The incremental finger refresh must refresh at least 1 finger"),
+            Self::InvalidRingBits(bits) => write!(f, "The number of bits of the ring must be in range 1..=64, got {}", bits),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::hasher::Sha1Hasher;
+    use super::*;
+
+    #[test]
+    fn it_should_mask_ids_to_ring_size() {
+        let ring = RingConfig::new(6);
+
+        assert_eq!(ring.mask(0), 0);
+        assert_eq!(ring.mask(63), 63);
+        assert_eq!(ring.mask(64), 0);
+        assert_eq!(ring.mask(u64::MAX), 63);
+        assert!(ring.id(b"127.0.0.1:42001") < 64);
+
+        let ring = RingConfig::new(1);
+        assert_eq!(ring.mask(3), 1);
+
+        let ring = RingConfig::default();
+        assert_eq!(ring.mask(u64::MAX), u64::MAX);
+        assert_eq!(ring.id(b"127.0.0.1:42001"), seahash::hash(b"127.0.0.1:42001"));
+    }
+
+    #[test]
+    fn it_should_derive_ids_with_configured_hasher() {
+        let ring = RingConfig::with_hasher(64, Sha1Hasher);
+        assert_eq!(ring.id(b"abc"), 0x7850c26c9cd0d89d);
+
+        let ring = RingConfig::with_hasher(16, Sha1Hasher);
+        assert_eq!(ring.id(b"abc"), 0xd89d);
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_should_reject_empty_ring() {
+        RingConfig::new(0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_should_reject_ring_larger_than_64_bits() {
+        RingConfig::new(65);
+    }
+
+    #[test]
+    fn it_should_reject_config_with_invalid_ring_bits() {
+        for bits in [0, 65] {
+            let config = Config { ring: RingConfig { bits, ..RingConfig::default() }, ..Config::default() };
+            assert_eq!(config.validate().unwrap_err(), ConfigError::InvalidRingBits(bits));
+        }
+
+        let config = Config { ring: RingConfig { bits: 1, ..RingConfig::default() }, ..Config::default() };
+        assert!(config.validate().is_ok());
+    }
+}
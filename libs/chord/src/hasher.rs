@@ -0,0 +1,63 @@
+use std::fmt::Debug;
+use sha1::{Digest, Sha1};
+
+/// Strategy used to derive ids on the ring from addresses and keys
+///
+/// The hasher returns the full 64 bit hash. It's masked to the identifier space of the ring
+/// by [`RingConfig::id`](crate::RingConfig::id).
+///
+/// All the nodes of a ring have to use the same hasher.
+pub trait IdHasher: Debug + Send + Sync {
+    /// Hash the given bytes
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The bytes to hash, e.g. an address of a node
+    fn hash(&self, bytes: &[u8]) -> u64;
+}
+
+/// [SeaHash](https://docs.rs/seahash) based hasher. It's the default hasher of the ring.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SeaHasher;
+
+impl IdHasher for SeaHasher {
+    fn hash(&self, bytes: &[u8]) -> u64 {
+        seahash::hash(bytes)
+    }
+}
+
+/// SHA-1 based hasher, as used in the Chord paper
+///
+/// The hash is the SHA-1 digest modulo `2^64`, i.e. its last 8 bytes read as a big-endian
+/// integer. Once masked to m bits, the id is the same as `SHA-1(bytes) mod 2^m`, which makes the
+/// ids compatible with other SHA-1 based Chord implementations.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sha1Hasher;
+
+impl IdHasher for Sha1Hasher {
+    fn hash(&self, bytes: &[u8]) -> u64 {
+        let digest = Sha1::digest(bytes);
+        let mut tail = [0_u8; 8];
+        tail.copy_from_slice(&digest[digest.len() - 8..]);
+
+        u64::from_be_bytes(tail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sea_hasher_should_use_seahash() {
+        assert_eq!(SeaHasher.hash(b"127.0.0.1:42001"), seahash::hash(b"127.0.0.1:42001"));
+    }
+
+    #[test]
+    fn sha1_hasher_should_use_last_8_bytes_of_digest() {
+        // SHA-1("abc") = a9993e364706816aba3e25717850c26c9cd0d89d
+        assert_eq!(Sha1Hasher.hash(b"abc"), 0x7850c26c9cd0d89d);
+        // SHA-1("") = da39a3ee5e6b4b0d3255bfef95601890afd80709
+        assert_eq!(Sha1Hasher.hash(b""), 0x95601890afd80709);
+    }
+}
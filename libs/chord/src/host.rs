@@ -0,0 +1,139 @@
+use std::net::SocketAddr;
+use crate::{Client, Config, Maintenance, MaintenanceConfig, Node, NodeHandle, NodeService};
+use crate::service::error::ServiceError;
+
+/// A physical host running several virtual nodes
+///
+/// Every virtual node has its own id and its own finger table, but all of them share the address
+/// of the host, so a single transport can serve them all and dispatch the requests by the id of
+/// the target node, see [`VirtualHost::node`]. A stronger machine can run more virtual nodes to
+/// take a larger part of the ring.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn run<C: chord_rs::Client + 'static>(bootstrap: chord_rs::Node) {
+/// use std::net::SocketAddr;
+/// use chord_rs::{Config, MaintenanceConfig, VirtualHost};
+///
+/// let host: VirtualHost<C> = VirtualHost::new(SocketAddr::from(([127, 0, 0, 1], 42000)), 4, Config::default());
+/// host.join(bootstrap).await.unwrap();
+///
+/// let mut maintenance = host.maintenance(MaintenanceConfig::default());
+/// maintenance.start();
+/// # }
+/// ```
+pub struct VirtualHost<C: Client + 'static> {
+    addr: SocketAddr,
+    nodes: Vec<NodeHandle<C>>,
+}
+
+impl<C: Client + 'static> VirtualHost<C> {
+    /// Create a new host with the given number of virtual nodes
+    ///
+    /// The ids of the virtual nodes are derived with [`Node::virtual_node`]. On a small ring two
+    /// indexes can hash to the same id, such a duplicate is skipped, so the host can run fewer
+    /// virtual nodes than requested.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address of the host, shared by all its virtual nodes
+    /// * `virtual_nodes` - The number of virtual nodes, e.g. based on the capacity of the host
+    /// * `config` - The configuration of every virtual node
+    ///
+    /// # Panics
+    ///
+    /// Panics if `virtual_nodes` is 0 or if the configuration is not valid, see
+    /// [`Config::validate`].
+    pub fn new(addr: SocketAddr, virtual_nodes: usize, config: Config) -> Self {
+        assert!(virtual_nodes > 0, "A host must run at least one virtual node");
+
+        let mut ids: Vec<u64> = Vec::with_capacity(virtual_nodes);
+        for index in 0..virtual_nodes {
+            let id = Node::virtual_node(addr, index, &config.ring).id;
+            if ids.contains(&id) {
+                log::warn!("Virtual node {} of host {} has the duplicate id {}, skipping it", index, addr, id);
+            } else {
+                ids.push(id);
+            }
+        }
+
+        let nodes = ids.into_iter()
+            .map(|id| NodeHandle::new(NodeService::with_id_and_config(id, addr, config.clone())))
+            .collect();
+
+        Self { addr, nodes }
+    }
+
+    /// Get the address of the host
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Get the handles of all the virtual nodes
+    pub fn nodes(&self) -> &[NodeHandle<C>] {
+        &self.nodes
+    }
+
+    /// Get the ids of all the virtual nodes
+    pub fn ids(&self) -> Vec<u64> {
+        self.nodes.iter().map(|node| node.node().id()).collect()
+    }
+
+    /// Get the virtual node with the given id
+    ///
+    /// It's used to dispatch an incoming request to the virtual node it's meant for.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the virtual node
+    pub fn node(&self, id: u64) -> Option<&NodeHandle<C>> {
+        self.nodes.iter().find(|node| node.node().id() == id)
+    }
+
+    /// Returns true if the given node is one of the virtual nodes of the host
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node to check
+    pub fn owns(&self, node: &Node) -> bool {
+        node.addr() == self.addr && self.node(node.id()).is_some()
+    }
+
+    /// Join the chord ring with all the virtual nodes
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node to join the ring with. It's an existing node in the ring.
+    pub async fn join(&self, node: Node) -> Result<(), ServiceError> {
+        for virtual_node in &self.nodes {
+            virtual_node.join(node.clone()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Leave the chord ring with all the virtual nodes
+    ///
+    /// > **Note**
+    /// >
+    /// > The host should not be used after leaving the ring.
+    pub async fn leave(&self) -> Result<(), ServiceError> {
+        for virtual_node in &self.nodes {
+            virtual_node.leave().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Create a maintenance runner for all the virtual nodes
+    ///
+    /// A single runner drives the routines of every virtual node of the host.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The intervals of the routines
+    pub fn maintenance(&self, config: MaintenanceConfig) -> Maintenance<C> {
+        Maintenance::for_nodes(self.nodes.clone(), config)
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{Client, KvStore, Node};
+use crate::kv::{MerkleTree, StorageError, MERKLE_TREE_DEPTH, TRANSFER_BATCH_SIZE};
+use crate::service::error::ServiceError;
+
+impl<C: Client> KvStore<C> {
+    /// Get the Merkle tree of the keys stored on the current node whose ids are in the range
+    /// `(from, to]`
+    ///
+    /// It's called by the RPC server on an incoming `merkle_tree` request.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The exclusive start of the range
+    /// * `to` - The inclusive end of the range
+    pub fn merkle_tree_local(&self, from: u64, to: u64) -> Result<MerkleTree, StorageError> {
+        let entries = self.entries_in_range(from, to)?;
+        let entries = entries.iter()
+            .map(|(key, versions)| (self.key_id(key), key.as_slice(), versions.as_slice()));
+
+        Ok(MerkleTree::build(self.node.config().ring.bits, from, to, MERKLE_TREE_DEPTH, entries))
+    }
+
+    /// Repair the replicas of the keys owned by the current node.
+    ///
+    /// The Merkle tree of the range `(predecessor, self]` is compared with the tree of every
+    /// replica. The keys of the ranges which differ are exchanged in both directions, so the
+    /// current node and the replica end up with all the versions known to either of them, the
+    /// tombstones of the deleted keys included. Nothing is exchanged when the trees are equal.
+    ///
+    /// A replica which fails doesn't keep the other replicas from being repaired, the first error
+    /// is returned once all of them were tried. Nothing is done while the predecessor is unknown.
+    ///
+    /// > **Note**
+    /// >
+    /// > This method should be called periodically, see [`Maintenance`](crate::Maintenance).
+    pub async fn anti_entropy(&self) -> Result<(), ServiceError> {
+        let factor = self.node.config().replication_factor;
+        let predecessor = match self.node.predecessor() {
+            Some(predecessor) if factor > 1 => predecessor,
+            _ => return Ok(()),
+        };
+
+        let node = self.node.node();
+        let replicas = Self::replica_set(node.clone(), self.node.successor_list(), factor);
+        let mut result = Ok(());
+        for replica in replicas.into_iter().skip(1) {
+            if let Err(err) = self.repair(&replica, predecessor.id(), node.id()).await {
+                log::debug!("Anti-entropy of node {} with node {} failed: {}", node.id(), replica.id(), err);
+                result = result.and(Err(err));
+            }
+        }
+
+        result
+    }
+
+    /// Exchange the keys of the range `(from, to]` which differ between the current node and the
+    /// replica
+    async fn repair(&self, replica: &Node, from: u64, to: u64) -> Result<(), ServiceError> {
+        let client: C = replica.client();
+        let remote = client.merkle_tree(from, to).await?;
+        let ranges = self.merkle_tree_local(from, to)?.diff(&remote);
+
+        for (from, to) in ranges {
+            let entries = self.entries_in_range(from, to)?;
+            for batch in entries.chunks(TRANSFER_BATCH_SIZE) {
+                client.replicate(batch.to_vec()).await?;
+            }
+
+            let mut after = None;
+            loop {
+                let batch = client.transfer_keys(from, to, after.take()).await?;
+                if batch.is_empty() {
+                    break;
+                }
+
+                after = batch.last().map(|(key, _)| key.clone());
+                self.replicate_local(batch)?;
+            }
+
+            log::debug!("Node {} repaired the range ({}, {}] with node {}", self.node.node().id(), from, to, replica.id());
+        }
+
+        Ok(())
+    }
+}
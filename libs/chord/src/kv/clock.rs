@@ -0,0 +1,306 @@
+use std::collections::BTreeMap;
+
+/// The causal relation of two vector clocks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Causality {
+    /// The clock happened before the other one
+    Before,
+    /// The clock happened after the other one
+    After,
+    /// Both clocks are the same
+    Equal,
+    /// Neither clock happened before the other one
+    Concurrent,
+}
+
+/// Vector clock of a value, keyed by the id of the node which coordinated the write
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VectorClock {
+    counters: BTreeMap<u64, u64>,
+}
+
+impl VectorClock {
+    /// Create an empty vector clock
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the counter of the given node
+    ///
+    /// # Arguments
+    ///
+    /// * `node_id` - The id of the node
+    pub fn get(&self, node_id: u64) -> u64 {
+        self.counters.get(&node_id).copied().unwrap_or(0)
+    }
+
+    /// Get the counters of all the nodes which have written the value
+    pub fn counters(&self) -> &BTreeMap<u64, u64> {
+        &self.counters
+    }
+
+    /// Increment the counter of the given node
+    ///
+    /// # Arguments
+    ///
+    /// * `node_id` - The id of the node coordinating the write
+    pub fn increment(&mut self, node_id: u64) {
+        *self.counters.entry(node_id).or_insert(0) += 1;
+    }
+
+    pub(crate) fn set(&mut self, node_id: u64, counter: u64) {
+        self.counters.insert(node_id, counter);
+    }
+
+    /// Merge the other clock into this one, keeping the highest counter of every node
+    ///
+    /// The merged clock descends from both clocks.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The clock to merge
+    pub fn merge(&mut self, other: &VectorClock) {
+        for (node_id, counter) in &other.counters {
+            let current = self.counters.entry(*node_id).or_insert(0);
+            *current = (*current).max(*counter);
+        }
+    }
+
+    /// Compare the clock with the other one
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The clock to compare with
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chord_rs::{Causality, VectorClock};
+    ///
+    /// let mut first = VectorClock::new();
+    /// first.increment(1);
+    ///
+    /// let mut second = first.clone();
+    /// second.increment(2);
+    /// assert_eq!(first.compare(&second), Causality::Before);
+    ///
+    /// first.increment(1);
+    /// assert_eq!(first.compare(&second), Causality::Concurrent);
+    /// ```
+    pub fn compare(&self, other: &VectorClock) -> Causality {
+        let nodes = self.counters.keys().chain(other.counters.keys());
+        let (mut before, mut after) = (false, false);
+        for node_id in nodes {
+            let (mine, theirs) = (self.get(*node_id), other.get(*node_id));
+            before |= mine < theirs;
+            after |= mine > theirs;
+        }
+
+        match (before, after) {
+            (false, false) => Causality::Equal,
+            (true, false) => Causality::Before,
+            (false, true) => Causality::After,
+            (true, true) => Causality::Concurrent,
+        }
+    }
+}
+
+/// Build a clock from the counters of the nodes, e.g. when it's received from another node
+impl FromIterator<(u64, u64)> for VectorClock {
+    fn from_iter<T: IntoIterator<Item = (u64, u64)>>(counters: T) -> Self {
+        Self { counters: counters.into_iter().collect() }
+    }
+}
+
+/// A value together with its vector clock
+///
+/// A version without a value is a tombstone written by a delete. It replaces the versions it
+/// descends from like any other version, so a replica which missed the delete can't bring the
+/// deleted value back.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Versioned {
+    /// The value, `None` for a tombstone
+    pub value: Option<Vec<u8>>,
+    pub clock: VectorClock,
+}
+
+impl Versioned {
+    /// Create a new versioned value
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value
+    /// * `clock` - The vector clock of the value
+    pub fn new(value: Vec<u8>, clock: VectorClock) -> Self {
+        Self { value: Some(value), clock }
+    }
+
+    /// Create a tombstone marking the versions it descends from as deleted
+    ///
+    /// # Arguments
+    ///
+    /// * `clock` - The vector clock of the delete
+    pub fn tombstone(clock: VectorClock) -> Self {
+        Self { value: None, clock }
+    }
+
+    /// Returns true if the version is a tombstone of a deleted value
+    pub fn is_tombstone(&self) -> bool {
+        self.value.is_none()
+    }
+
+    /// Add the version to the siblings of a key
+    ///
+    /// The siblings the version descends from are dropped. The version itself is dropped when it
+    /// is already known or one of the siblings descends from it. Versions with the same clock but
+    /// a different value were written concurrently, so both of them are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `siblings` - The concurrent versions of the key
+    /// * `version` - The version to add
+    ///
+    /// Returns false if the version was dropped, so the siblings didn't change.
+    pub(crate) fn reconcile(siblings: &mut Vec<Versioned>, version: Versioned) -> bool {
+        let obsolete = siblings.iter().any(|sibling| match sibling.clock.compare(&version.clock) {
+            Causality::After => true,
+            Causality::Equal => sibling.value == version.value,
+            _ => false,
+        });
+        if obsolete {
+            return false;
+        }
+
+        siblings.retain(|sibling| sibling.clock.compare(&version.clock) != Causality::Before);
+        siblings.push(version);
+        true
+    }
+}
+
+/// The versions of a key returned by a read
+///
+/// A key has more than one version, called siblings, when it was written concurrently. The
+/// conflict is resolved by writing the merged value with the [`Versions::context`] of the read.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Versions {
+    siblings: Vec<Versioned>,
+}
+
+impl Versions {
+    /// Create the versions from the siblings of a key
+    ///
+    /// # Arguments
+    ///
+    /// * `siblings` - The concurrent versions of the key
+    pub fn new(siblings: Vec<Versioned>) -> Self {
+        Self { siblings }
+    }
+
+    /// Get all the concurrent versions of the key
+    pub fn siblings(&self) -> &[Versioned] {
+        &self.siblings
+    }
+
+    /// Get the values of all the concurrent versions of the key, without the tombstones
+    pub fn values(&self) -> Vec<&[u8]> {
+        self.siblings.iter().filter_map(|sibling| sibling.value.as_deref()).collect()
+    }
+
+    /// Get the value of the key, if it has a single version which is not a tombstone
+    pub fn value(&self) -> Option<&[u8]> {
+        match self.siblings.as_slice() {
+            [version] => version.value.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the key was written concurrently and has several versions
+    pub fn is_conflict(&self) -> bool {
+        self.siblings.len() > 1
+    }
+
+    /// Returns true if the key was not found or it was deleted
+    pub fn is_empty(&self) -> bool {
+        self.siblings.iter().all(Versioned::is_tombstone)
+    }
+
+    /// Get the clock descending from all the versions
+    ///
+    /// A write with this context replaces all the versions returned by the read.
+    pub fn context(&self) -> VectorClock {
+        let mut context = VectorClock::new();
+        for sibling in &self.siblings {
+            context.merge(&sibling.clock);
+        }
+
+        context
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock(counters: &[(u64, u64)]) -> VectorClock {
+        VectorClock { counters: counters.iter().copied().collect() }
+    }
+
+    #[test]
+    fn it_should_compare_clocks() {
+        assert_eq!(clock(&[]).compare(&clock(&[])), Causality::Equal);
+        assert_eq!(clock(&[(1, 1)]).compare(&clock(&[(1, 1), (2, 0)])), Causality::Equal);
+        assert_eq!(clock(&[(1, 1)]).compare(&clock(&[(1, 2)])), Causality::Before);
+        assert_eq!(clock(&[(1, 1), (2, 1)]).compare(&clock(&[(1, 1)])), Causality::After);
+        assert_eq!(clock(&[(1, 2)]).compare(&clock(&[(1, 1), (2, 1)])), Causality::Concurrent);
+    }
+
+    #[test]
+    fn it_should_merge_clocks() {
+        let mut merged = clock(&[(1, 2), (2, 1)]);
+        merged.merge(&clock(&[(1, 1), (3, 4)]));
+
+        assert_eq!(merged, clock(&[(1, 2), (2, 1), (3, 4)]));
+    }
+
+    #[test]
+    fn it_should_keep_concurrent_siblings() {
+        let mut siblings = Vec::new();
+        Versioned::reconcile(&mut siblings, Versioned::new(b"a".to_vec(), clock(&[(1, 1)])));
+        Versioned::reconcile(&mut siblings, Versioned::new(b"b".to_vec(), clock(&[(2, 1)])));
+        Versioned::reconcile(&mut siblings, Versioned::new(b"c".to_vec(), clock(&[(2, 1)])));
+        Versioned::reconcile(&mut siblings, Versioned::new(b"c".to_vec(), clock(&[(2, 1)])));
+        assert_eq!(siblings.len(), 3);
+
+        // An outdated version is ignored
+        Versioned::reconcile(&mut siblings, Versioned::new(b"d".to_vec(), clock(&[])));
+        assert_eq!(siblings.len(), 3);
+
+        // A version descending from all the siblings replaces them
+        let versions = Versions::new(siblings.clone());
+        let mut context = versions.context();
+        context.increment(1);
+        Versioned::reconcile(&mut siblings, Versioned::new(b"abc".to_vec(), context));
+
+        assert_eq!(Versions::new(siblings).value(), Some(b"abc".as_slice()));
+    }
+
+    #[test]
+    fn tombstone_should_replace_versions_it_descends_from() {
+        let mut siblings = vec![Versioned::new(b"a".to_vec(), clock(&[(1, 1)]))];
+        Versioned::reconcile(&mut siblings, Versioned::tombstone(clock(&[(1, 2)])));
+
+        let versions = Versions::new(siblings.clone());
+        assert!(versions.is_empty());
+        assert_eq!(versions.value(), None);
+        assert_eq!(versions.context(), clock(&[(1, 2)]));
+
+        // The deleted value doesn't come back from a replica which missed the delete
+        assert!(!Versioned::reconcile(&mut siblings, Versioned::new(b"a".to_vec(), clock(&[(1, 1)]))));
+
+        // A concurrent write is kept next to the tombstone
+        Versioned::reconcile(&mut siblings, Versioned::new(b"b".to_vec(), clock(&[(2, 1)])));
+        let versions = Versions::new(siblings);
+        assert!(!versions.is_empty());
+        assert_eq!(versions.values(), vec![b"b".as_slice()]);
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tokio::time::Instant;
+use crate::{Client, KvStore, Node};
+use crate::service::error::ServiceError;
+use crate::kv::Versioned;
+
+/// A write to an unreachable replica, kept until the replica is back
+struct Hint {
+    target: Node,
+    key: Vec<u8>,
+    version: Versioned,
+    expires: Instant,
+}
+
+/// Bounded queue of the hints stored on the node coordinating the writes
+pub(crate) struct Hints {
+    queue: Mutex<VecDeque<Hint>>,
+    capacity: usize,
+    ttl: Duration,
+}
+
+impl Hints {
+    pub(crate) fn new(capacity: usize, ttl: Duration) -> Self {
+        Self { queue: Mutex::new(VecDeque::new()), capacity, ttl }
+    }
+
+    /// Store the write for the target replica.
+    ///
+    /// Returns false if the queue is full, so the write is lost.
+    pub(crate) fn push(&self, target: Node, key: Vec<u8>, version: Versioned) -> bool {
+        let mut queue = self.queue();
+        Self::drop_expired(&mut queue);
+        if queue.len() >= self.capacity {
+            return false;
+        }
+
+        queue.push_back(Hint { target, key, version, expires: Instant::now() + self.ttl });
+        true
+    }
+
+    fn len(&self) -> usize {
+        let mut queue = self.queue();
+        Self::drop_expired(&mut queue);
+        queue.len()
+    }
+
+    /// Get the replicas which have pending hints
+    fn targets(&self) -> Vec<Node> {
+        let mut queue = self.queue();
+        Self::drop_expired(&mut queue);
+
+        let mut targets: Vec<Node> = Vec::new();
+        for hint in queue.iter() {
+            if !targets.contains(&hint.target) {
+                targets.push(hint.target.clone());
+            }
+        }
+
+        targets
+    }
+
+    /// Remove the hints of the target replica from the queue
+    fn take(&self, target: &Node) -> Vec<Hint> {
+        let mut queue = self.queue();
+        let (taken, kept): (VecDeque<Hint>, VecDeque<Hint>) = queue.drain(..).partition(|hint| hint.target == *target);
+        *queue = kept;
+
+        taken.into()
+    }
+
+    /// Put back the hints which couldn't be delivered, unless the queue filled up in the meantime
+    fn restore(&self, hints: impl IntoIterator<Item = Hint>) {
+        let mut queue = self.queue();
+        for hint in hints {
+            if queue.len() >= self.capacity {
+                break;
+            }
+
+            queue.push_back(hint);
+        }
+    }
+
+    fn drop_expired(queue: &mut VecDeque<Hint>) {
+        let now = Instant::now();
+        queue.retain(|hint| hint.expires > now);
+    }
+
+    // Every update of the queue adds or removes whole hints, so a panic while holding the lock
+    // can't leave it inconsistent.
+    fn queue(&self) -> MutexGuard<'_, VecDeque<Hint>> {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<C: Client> KvStore<C> {
+    /// Get the number of writes waiting on the current node for their unreachable replicas
+    pub fn pending_hints(&self) -> usize {
+        self.hints.len()
+    }
+
+    /// Deliver the hints of the replicas which are reachable again.
+    ///
+    /// Every replica with pending hints is pinged first. The hints of a replica which answers are
+    /// written to it in the order they were stored, the hints which fail are kept for the next
+    /// call. Hints older than [`hint_ttl`](crate::Config::hint_ttl) are dropped.
+    ///
+    /// > **Note**
+    /// >
+    /// > This method should be called periodically, see [`Maintenance`](crate::Maintenance).
+    pub async fn replay_hints(&self) -> Result<(), ServiceError> {
+        let mut result = Ok(());
+        for target in self.hints.targets() {
+            if let Err(err) = self.deliver_hints(&target).await {
+                log::debug!("Delivering hints to node {} failed: {}", target.id(), err);
+                result = result.and(Err(err));
+            }
+        }
+
+        result
+    }
+
+    async fn deliver_hints(&self, target: &Node) -> Result<(), ServiceError> {
+        let client: C = target.client();
+        if let Err(err) = client.ping().await {
+            log::debug!("Node {} with pending hints is still unreachable: {}", target.id(), err);
+            return Ok(());
+        }
+
+        let hints = self.hints.take(target);
+        let total = hints.len();
+        let mut hints = hints.into_iter();
+        while let Some(hint) = hints.next() {
+            let (key, version) = (hint.key.clone(), hint.version.clone());
+            if let Err(err) = client.put(key, version).await {
+                self.hints.restore(std::iter::once(hint).chain(hints));
+                return Err(err.into());
+            }
+        }
+
+        log::debug!("Node {} delivered {} hints to node {}", self.node.node().id(), total, target.id());
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+use crate::kv::Versioned;
+
+/// Number of levels below the root of the Merkle tree, the tree has `2^depth` leaves
+pub const MERKLE_TREE_DEPTH: u8 = 10;
+
+/// Merkle tree over the keys of a range of the ring
+///
+/// The range `(from, to]` is split into `2^depth` leaves of the same size. Every leaf holds the
+/// hash of the keys whose ids fall into it, together with all their versions, and every inner node
+/// holds the hash of its children. Two replicas compare their trees from the root down, so only
+/// the leaves which differ have to be exchanged.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MerkleTree {
+    bits: u8,
+    from: u64,
+    to: u64,
+    depth: u8,
+    /// The hashes of the nodes, level by level. The root is at index 0 and the children of the
+    /// node `i` are at `2i + 1` and `2i + 2`.
+    hashes: Vec<u64>,
+}
+
+impl MerkleTree {
+    /// Build the tree over the keys of the range `(from, to]`
+    ///
+    /// The range covers the whole ring when `from == to`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bits` - The number of bits of the identifiers of the ring
+    /// * `from` - The exclusive start of the range
+    /// * `to` - The inclusive end of the range
+    /// * `depth` - The number of levels below the root
+    /// * `entries` - The ids of the keys in the range, with the keys and their versions
+    pub(crate) fn build<'a>(bits: u8, from: u64, to: u64, depth: u8,
+                            entries: impl IntoIterator<Item = (u64, &'a [u8], &'a [Versioned])>) -> Self {
+        let leaves = 1usize << depth;
+        let mut tree = Self { bits, from, to, depth, hashes: vec![0; 2 * leaves - 1] };
+
+        for (id, key, versions) in entries {
+            let leaf = tree.leaf(id);
+            tree.hashes[leaves - 1 + leaf] = tree.hashes[leaves - 1 + leaf].wrapping_add(entry_hash(key, versions));
+        }
+
+        for index in (0..leaves - 1).rev() {
+            let (left, right) = (tree.hashes[2 * index + 1], tree.hashes[2 * index + 2]);
+            tree.hashes[index] = node_hash(left, right);
+        }
+
+        tree
+    }
+
+    /// Create the tree from its parts, e.g. when it's received from another node
+    ///
+    /// # Arguments
+    ///
+    /// * `bits` - The number of bits of the identifiers of the ring
+    /// * `from` - The exclusive start of the range
+    /// * `to` - The inclusive end of the range
+    /// * `depth` - The number of levels below the root
+    /// * `hashes` - The hashes of the nodes, level by level starting with the root
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of hashes doesn't match the depth.
+    pub fn from_parts(bits: u8, from: u64, to: u64, depth: u8, hashes: Vec<u64>) -> Self {
+        assert_eq!(hashes.len(), (2 << depth) - 1, "A Merkle tree of depth {} has {} nodes", depth, (2 << depth) - 1);
+        Self { bits, from, to, depth, hashes }
+    }
+
+    /// Get the number of bits of the identifiers of the ring
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    /// Get the exclusive start of the range
+    pub fn from(&self) -> u64 {
+        self.from
+    }
+
+    /// Get the inclusive end of the range
+    pub fn to(&self) -> u64 {
+        self.to
+    }
+
+    /// Get the number of levels below the root
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// Get the hashes of the nodes, level by level starting with the root
+    pub fn hashes(&self) -> &[u64] {
+        &self.hashes
+    }
+
+    /// Get the hash of the root, which covers all the keys of the range
+    pub fn root(&self) -> u64 {
+        self.hashes[0]
+    }
+
+    /// Get the ranges of the ring whose keys differ between the two trees
+    ///
+    /// The trees are compared from the root down and only the subtrees with different hashes are
+    /// visited. Adjacent leaves are merged into a single range. The whole range is returned when
+    /// the trees don't cover the same range with the same depth.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The tree to compare with
+    pub fn diff(&self, other: &MerkleTree) -> Vec<(u64, u64)> {
+        if (self.bits, self.from, self.to, self.depth) != (other.bits, other.from, other.to, other.depth) {
+            return vec![(self.from, self.to)];
+        }
+
+        let leaves = 1usize << self.depth;
+        let mut ranges: Vec<(u64, u64)> = Vec::new();
+        let mut pending = vec![0];
+        while let Some(index) = pending.pop() {
+            if self.hashes[index] == other.hashes[index] {
+                continue;
+            }
+
+            if index < leaves - 1 {
+                // The right child is visited last, so the leaves come out in order
+                pending.push(2 * index + 2);
+                pending.push(2 * index + 1);
+                continue;
+            }
+
+            let (start, end) = self.leaf_range(index + 1 - leaves);
+            match ranges.last_mut() {
+                Some(last) if last.1 == start => last.1 = end,
+                _ => ranges.push((start, end)),
+            }
+        }
+
+        ranges
+    }
+
+    /// The number of ids of the ring in the range
+    fn len(&self) -> u128 {
+        let size = 1u128 << self.bits;
+        match (self.to as u128 + size - self.from as u128) % size {
+            0 => size,
+            len => len,
+        }
+    }
+
+    /// Get the index of the leaf of the given id of the range
+    fn leaf(&self, id: u64) -> usize {
+        let size = 1u128 << self.bits;
+        let offset = match (id as u128 + size - self.from as u128) % size {
+            0 => size,
+            offset => offset,
+        };
+
+        ((offset * (1u128 << self.depth) - 1) / self.len()) as usize
+    }
+
+    /// Get the range `(start, end]` of the ids of the given leaf
+    fn leaf_range(&self, leaf: usize) -> (u64, u64) {
+        let (len, leaves) = (self.len(), 1u128 << self.depth);
+        let mask = (1u128 << self.bits) - 1;
+        let start = (self.from as u128 + leaf as u128 * len / leaves) & mask;
+        let end = (self.from as u128 + (leaf as u128 + 1) * len / leaves) & mask;
+
+        (start as u64, end as u64)
+    }
+}
+
+/// Hash the key with all its versions, regardless of the order of the siblings
+fn entry_hash(key: &[u8], versions: &[Versioned]) -> u64 {
+    let versions = versions.iter().fold(0u64, |hash, version| {
+        // A tombstone has to differ from an empty value
+        let mut bytes = match &version.value {
+            Some(value) => [&[1], value.as_slice()].concat(),
+            None => vec![0],
+        };
+        for (node_id, counter) in version.clock.counters() {
+            bytes.extend_from_slice(&node_id.to_le_bytes());
+            bytes.extend_from_slice(&counter.to_le_bytes());
+        }
+
+        hash.wrapping_add(seahash::hash(&bytes))
+    });
+
+    let mut bytes = key.to_vec();
+    bytes.extend_from_slice(&versions.to_le_bytes());
+    seahash::hash(&bytes)
+}
+
+fn node_hash(left: u64, right: u64) -> u64 {
+    let mut bytes = [0; 16];
+    bytes[..8].copy_from_slice(&left.to_le_bytes());
+    bytes[8..].copy_from_slice(&right.to_le_bytes());
+    seahash::hash(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::VectorClock;
+    use super::*;
+
+    fn version(value: &[u8], node_id: u64) -> Versioned {
+        let mut clock = VectorClock::new();
+        clock.increment(node_id);
+        Versioned::new(value.to_vec(), clock)
+    }
+
+    fn tree(from: u64, to: u64, entries: &[(u64, Vec<Versioned>)]) -> MerkleTree {
+        let keys: Vec<Vec<u8>> = entries.iter().map(|(id, _)| id.to_string().into_bytes()).collect();
+        let entries = entries.iter().zip(&keys).map(|((id, versions), key)| (*id, key.as_slice(), versions.as_slice()));
+        MerkleTree::build(6, from, to, 2, entries)
+    }
+
+    #[test]
+    fn it_should_split_range_into_leaves() {
+        let range = tree(60, 12, &[]);
+
+        assert_eq!(range.leaf(61), 0);
+        assert_eq!(range.leaf(63), 0);
+        assert_eq!(range.leaf(0), 0);
+        assert_eq!(range.leaf(1), 1);
+        assert_eq!(range.leaf(5), 2);
+        assert_eq!(range.leaf(12), 3);
+        assert_eq!(range.leaf_range(0), (60, 0));
+        assert_eq!(range.leaf_range(3), (8, 12));
+
+        let ring = tree(5, 5, &[]);
+        assert_eq!(ring.leaf(6), 0);
+        assert_eq!(ring.leaf(5), 3);
+        assert_eq!(ring.leaf_range(3), (53, 5));
+    }
+
+    #[test]
+    fn equal_trees_should_not_differ() {
+        let first = tree(0, 32, &[(4, vec![version(b"a", 1), version(b"b", 2)]), (20, vec![version(b"c", 1)])]);
+        let second = tree(0, 32, &[(20, vec![version(b"c", 1)]), (4, vec![version(b"b", 2), version(b"a", 1)])]);
+
+        assert_eq!(first.root(), second.root());
+        assert!(first.diff(&second).is_empty());
+    }
+
+    #[test]
+    fn it_should_find_differing_leaves() {
+        let first = tree(0, 32, &[(4, vec![version(b"a", 1)]), (10, vec![version(b"b", 1)]), (20, vec![version(b"c", 1)])]);
+        let second = tree(0, 32, &[(4, vec![version(b"a", 2)]), (10, vec![version(b"b", 1)]), (12, vec![version(b"d", 1)])]);
+
+        // The leaves (0, 8], (8, 16] and (16, 24] differ
+        assert_eq!(first.diff(&second), vec![(0, 24)]);
+
+        let third = tree(0, 32, &[(4, vec![version(b"a", 1)]), (10, vec![version(b"b", 1)]), (28, vec![version(b"e", 1)])]);
+        assert_eq!(first.diff(&third), vec![(16, 32)]);
+
+        let fourth = tree(0, 32, &[(4, vec![version(b"a", 2)]), (10, vec![version(b"b", 1)])]);
+        assert_eq!(first.diff(&fourth), vec![(0, 8), (16, 24)]);
+
+        let other_range = tree(4, 32, &[]);
+        assert_eq!(first.diff(&other_range), vec![(0, 32)]);
+    }
+}
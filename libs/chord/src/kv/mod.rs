@@ -0,0 +1,340 @@
+#[cfg(test)]
+mod tests;
+mod anti_entropy;
+mod clock;
+mod handoff;
+mod merkle;
+mod quorum;
+mod replication;
+mod scan;
+mod storage;
+mod tombstone;
+
+pub use clock::{Causality, VectorClock, Versioned, Versions};
+pub use merkle::{MerkleTree, MERKLE_TREE_DEPTH};
+pub use quorum::Consistency;
+pub use scan::{ScanPage, ScanToken};
+pub use storage::{MemoryBackend, StorageBackend, StorageError, WalBackend};
+
+use std::sync::{Arc, Mutex, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use crate::{Client, Node, NodeHandle};
+use crate::kv::handoff::Hints;
+use crate::kv::replication::ReplicaSet;
+use crate::kv::tombstone::Tombstones;
+use crate::service::error::ServiceError;
+
+/// A key with all its concurrent versions
+pub type Entry = (Vec<u8>, Vec<Versioned>);
+
+/// Maximum number of keys sent in a single batch of a key transfer
+pub const TRANSFER_BATCH_SIZE: usize = 256;
+
+/// Key-value store on top of the chord ring
+///
+/// Every key is hashed onto the ring with the hasher of the ring and it's stored on the
+/// successor of its id. The store either serves the request from its local data, when the
+/// current node is the successor, or forwards it to the successor with the [`Client`].
+///
+/// The key is replicated to the following nodes of the successor list, according to the
+/// configured [`replication_factor`](crate::Config::replication_factor), see
+/// [`KvStore::replicas`]. Every read and write waits for the number of replicas given by its
+/// [`Consistency`] level.
+///
+/// The keys are kept in a [`StorageBackend`], in memory by default or in a write-ahead log with
+/// [`KvStore::with_backend`].
+///
+/// Every value carries a [`VectorClock`]. Values written concurrently are kept side by side as
+/// siblings and returned together by a read, until a write resolves them, see [`Versions`].
+///
+/// A delete writes a tombstone, a version without a value, which replaces the versions of the key
+/// like any other write. The deleted keys are dropped after the
+/// [`tombstone_ttl`](crate::Config::tombstone_ttl), see [`KvStore::collect_tombstones`].
+///
+/// A write to a replica which can't be reached is kept as a hint on the node coordinating the
+/// write and it's delivered once the replica is back, see [`KvStore::replay_hints`]. Replicas
+/// which missed writes anyway, e.g. because they were down for too long, are repaired by
+/// [`KvStore::anti_entropy`], which compares the [`MerkleTree`] of the keys with every replica.
+///
+/// The keys of a range of the ring are read page by page in the order of the ring with
+/// [`KvStore::scan`].
+///
+/// The RPC server of the node passes the incoming requests to [`KvStore::put_local`],
+/// [`KvStore::get_local`], [`KvStore::transfer_keys_local`],
+/// [`KvStore::confirm_transfer_local`], [`KvStore::replicate_local`],
+/// [`KvStore::merkle_tree_local`] and [`KvStore::scan_local`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn run<C: chord_rs::Client + 'static>(node: chord_rs::NodeHandle<C>) {
+/// use chord_rs::KvStore;
+///
+/// let store = KvStore::new(node);
+/// store.put(b"key", b"value".to_vec()).await.unwrap();
+///
+/// assert_eq!(store.get(b"key").await.unwrap().value(), Some(b"value".as_slice()));
+/// # }
+/// ```
+pub struct KvStore<C: Client> {
+    node: NodeHandle<C>,
+    data: RwLock<Box<dyn StorageBackend>>,
+    replication: Mutex<Option<ReplicaSet>>,
+    counter: Mutex<u64>,
+    hints: Arc<Hints>,
+    tombstones: Tombstones,
+}
+
+impl<C: Client> KvStore<C> {
+    /// Create a new key-value store served by the given node, keeping the keys in memory
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node serving the store
+    pub fn new(node: impl Into<NodeHandle<C>>) -> Self {
+        Self::with_backend(node, MemoryBackend::new())
+    }
+
+    /// Create a new key-value store served by the given node, keeping the keys in the given
+    /// storage backend
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node serving the store
+    /// * `backend` - The storage of the keys, e.g. a [`WalBackend`] to keep them across restarts
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys stored in the backend can't be read.
+    pub fn with_backend(node: impl Into<NodeHandle<C>>, backend: impl StorageBackend + 'static) -> Self {
+        let node = node.into();
+        // The clocks issued before a restart are only found in the stored versions
+        let counter = highest_counter(&backend, node.node().id());
+        let hints = Hints::new(node.config().hint_capacity, node.config().hint_ttl);
+        let tombstones = Tombstones::new(node.config().tombstone_ttl);
+        Self {
+            node,
+            data: RwLock::new(Box::new(backend)),
+            replication: Mutex::new(None),
+            counter: Mutex::new(counter),
+            hints: Arc::new(hints),
+            tombstones,
+        }
+    }
+
+    /// Get the handle of the node serving the store
+    pub fn node(&self) -> &NodeHandle<C> {
+        &self.node
+    }
+
+    /// Get the id of the given key on the ring
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to get the id for
+    pub fn key_id(&self, key: &[u8]) -> u64 {
+        self.node.config().ring.id(key)
+    }
+
+    /// Join the chord ring and take over the keys of the new key range.
+    ///
+    /// The node joins the ring with [`NodeService::join`](crate::NodeService::join) and pulls
+    /// every key in the range `(predecessor, self]` from its successor, batch by batch. The
+    /// successor keeps the keys until the transfer is confirmed, so a failed transfer doesn't
+    /// lose any data.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node to join the ring with. It's an existing node in the ring.
+    pub async fn join(&self, node: Node) -> Result<(), ServiceError> {
+        self.node.join(node).await?;
+        self.pull_keys().await
+    }
+
+    /// Store the version of the key on the current node
+    ///
+    /// It's called by the RPC server on an incoming `put` request. The version replaces the
+    /// versions it descends from and it's kept as a sibling of the concurrent ones. The version is
+    /// a tombstone when the key is deleted.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to store the value for
+    /// * `version` - The version to store
+    pub fn put_local(&self, key: Vec<u8>, version: Versioned) -> Result<(), StorageError> {
+        self.merge_versions(self.write_data().as_mut(), key, vec![version])
+    }
+
+    /// Get all the versions of the key stored on the current node, including the tombstones
+    ///
+    /// It's called by the RPC server on an incoming `get` request.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to get the versions for
+    pub fn get_local(&self, key: &[u8]) -> Result<Vec<Versioned>, StorageError> {
+        Ok(self.read_data().get(self.key_id(key), key)?.unwrap_or_default())
+    }
+
+    /// Get the next batch of the keys stored on the current node whose ids are in the range
+    /// `(from, to]`
+    ///
+    /// It's called by the RPC server on an incoming `transfer_keys` request. The keys are kept
+    /// until the transfer is confirmed with [`KvStore::confirm_transfer_local`].
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The exclusive start of the range
+    /// * `to` - The inclusive end of the range
+    /// * `after` - The last key of the previous batch, `None` for the first batch
+    pub fn transfer_keys_local(&self, from: u64, to: u64, after: Option<&[u8]>) -> Result<Vec<Entry>, StorageError> {
+        let after = after.map(|key| (self.key_id(key), key));
+        self.read_data().range(from, to, after, TRANSFER_BATCH_SIZE)
+    }
+
+    /// Drop the versions which were transferred to another node
+    ///
+    /// It's called by the RPC server on an incoming `confirm_transfer` request. Only the versions
+    /// the transferred ones descend from are dropped, so a version written while the keys were
+    /// transferred is kept, and the key is deleted once none of its versions is left.
+    ///
+    /// # Arguments
+    ///
+    /// * `transferred` - The transferred keys with their versions
+    pub fn confirm_transfer_local(&self, transferred: Vec<Entry>) -> Result<(), StorageError> {
+        let mut data = self.write_data();
+        for (key, versions) in transferred {
+            let id = self.key_id(&key);
+            let siblings = data.get(id, &key)?.unwrap_or_default();
+            let count = siblings.len();
+            let kept: Vec<Versioned> = siblings.into_iter()
+                .filter(|sibling| !versions.iter().any(|version| {
+                    matches!(sibling.clock.compare(&version.clock), Causality::Before | Causality::Equal)
+                }))
+                .collect();
+
+            if kept.is_empty() {
+                data.delete(id, &key)?;
+            } else if kept.len() < count {
+                data.put(id, key, kept)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the number of keys stored on the current node, including the deleted keys whose
+    /// tombstones weren't dropped yet
+    pub fn len(&self) -> usize {
+        self.read_data().len()
+    }
+
+    /// Returns true if no key is stored on the current node
+    pub fn is_empty(&self) -> bool {
+        self.read_data().is_empty()
+    }
+
+    /// Get the clock of a new version written through the current node
+    ///
+    /// The counter of the current node is higher than in any clock it issued before, also before
+    /// a restart of a persistent store, so the new version never looks older than a version
+    /// written through the current node.
+    fn next_clock(&self, context: &VectorClock) -> VectorClock {
+        let id = self.node.node().id();
+        let mut counter = self.counter.lock().unwrap_or_else(PoisonError::into_inner);
+        *counter = (*counter).max(context.get(id)) + 1;
+
+        let mut clock = context.clone();
+        clock.set(id, *counter);
+        clock
+    }
+
+    /// Get all the keys stored on the current node whose ids are in the range `(from, to]`
+    fn entries_in_range(&self, from: u64, to: u64) -> Result<Vec<Entry>, StorageError> {
+        self.read_data().range(from, to, None, usize::MAX)
+    }
+
+    /// Merge the versions into the siblings of the key, the key is only written if they changed
+    fn merge_versions(&self, data: &mut dyn StorageBackend, key: Vec<u8>, versions: Vec<Versioned>)
+        -> Result<(), StorageError> {
+        let id = self.key_id(&key);
+        let mut siblings = data.get(id, &key)?.unwrap_or_default();
+        let mut changed = false;
+        for version in versions {
+            changed |= Versioned::reconcile(&mut siblings, version);
+        }
+
+        if changed {
+            data.put(id, key, siblings)?;
+        }
+
+        Ok(())
+    }
+
+    /// Pull the keys of the range `(predecessor, self]` from the successor.
+    ///
+    /// Right after the join the current node doesn't know its predecessor yet, it's the
+    /// predecessor of the successor until the ring stabilizes, so the successor is asked for it.
+    /// The replicas of the ranges before the predecessor stay on the successor. Without a
+    /// predecessor the successor is alone in the ring, so all its keys which aren't in
+    /// `(self, successor]` are the keys of the current node.
+    ///
+    /// When the keys are replicated, the successor becomes a replica of the keys, so it keeps
+    /// them and the transfer is not confirmed.
+    async fn pull_keys(&self) -> Result<(), ServiceError> {
+        let node = self.node.node();
+        let successor = self.node.successor_list().first().cloned()
+            .ok_or_else(|| ServiceError::Unexpected("The node has no successor".to_string()))?;
+        if successor == node {
+            return Ok(());
+        }
+
+        let client: C = successor.client();
+        let predecessor = match self.node.predecessor() {
+            Some(predecessor) => Some(predecessor),
+            None => client.predecessor().await?,
+        };
+        let from = predecessor.filter(|predecessor| *predecessor != node).unwrap_or(successor.clone()).id();
+
+        let mut transferred = Vec::new();
+        let mut after = None;
+        loop {
+            let batch = client.transfer_keys(from, node.id(), after.take()).await?;
+            if batch.is_empty() {
+                break;
+            }
+
+            after = batch.last().map(|(key, _)| key.clone());
+            transferred.extend(batch.iter().cloned());
+            self.replicate_local(batch)?;
+        }
+
+        log::debug!("Node {} took over {} keys from node {}", node.id(), transferred.len(), successor.id());
+        // The successor stays a replica of the keys, unless the keys are not replicated at all
+        let replicated = self.node.config().replication_factor > 1 && !successor.is_same_host(&node);
+        if !transferred.is_empty() && !replicated {
+            client.confirm_transfer(transferred).await?;
+        }
+
+        Ok(())
+    }
+
+    // Every update of the data replaces the versions of a single key, so a panic while holding
+    // the lock can't leave the data half updated.
+    fn read_data(&self) -> RwLockReadGuard<'_, Box<dyn StorageBackend>> {
+        self.data.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write_data(&self) -> RwLockWriteGuard<'_, Box<dyn StorageBackend>> {
+        self.data.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Get the highest counter of the node in the clocks of the stored versions
+fn highest_counter(backend: &dyn StorageBackend, node_id: u64) -> u64 {
+    let entries = backend.range(0, 0, None, usize::MAX).expect("Reading the stored versions failed");
+    entries.iter()
+        .flat_map(|(_, versions)| versions)
+        .map(|version| version.clock.get(node_id))
+        .max()
+        .unwrap_or(0)
+}
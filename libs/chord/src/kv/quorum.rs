@@ -0,0 +1,266 @@
+use std::future::Future;
+use tokio::task::JoinSet;
+use crate::{Client, KvStore, Node, StorageError, VectorClock, Versioned, Versions};
+use crate::client::ClientError;
+use crate::service::error::ServiceError;
+
+/// The number of replicas which have to acknowledge a read or a write
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Consistency {
+    /// A single replica
+    One,
+    /// The majority of the replicas
+    Quorum,
+    /// All the replicas
+    All,
+}
+
+impl Consistency {
+    /// Get the number of replicas which have to acknowledge the request
+    ///
+    /// # Arguments
+    ///
+    /// * `replicas` - The number of replicas of the key
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chord_rs::Consistency;
+    ///
+    /// assert_eq!(Consistency::One.required(3), 1);
+    /// assert_eq!(Consistency::Quorum.required(3), 2);
+    /// assert_eq!(Consistency::Quorum.required(4), 3);
+    /// assert_eq!(Consistency::All.required(3), 3);
+    /// ```
+    pub fn required(&self, replicas: usize) -> usize {
+        match self {
+            Consistency::One => replicas.min(1),
+            Consistency::Quorum => replicas / 2 + 1,
+            Consistency::All => replicas,
+        }
+    }
+}
+
+impl<C: Client + 'static> KvStore<C> {
+    /// Store the value of the key on all its replicas and wait for all of them
+    ///
+    /// It's the same as [`KvStore::put_with`] with [`Consistency::All`].
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to store the value for
+    /// * `value` - The value to store
+    pub async fn put(&self, key: &[u8], value: Vec<u8>) -> Result<(), ServiceError> {
+        self.put_with(key, value, Consistency::All).await
+    }
+
+    /// Store the value of the key on all its replicas, without reading it first
+    ///
+    /// It's the same as [`KvStore::put_with_context`] with an empty context, so the value replaces
+    /// only the earlier versions written through the current node. It's kept as a sibling of the
+    /// versions written through the other nodes.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to store the value for
+    /// * `value` - The value to store
+    /// * `consistency` - The number of replicas which have to acknowledge the write
+    pub async fn put_with(&self, key: &[u8], value: Vec<u8>, consistency: Consistency) -> Result<(), ServiceError> {
+        self.put_with_context(key, value, &VectorClock::new(), consistency).await
+    }
+
+    /// Store the value of the key on all its replicas, replacing the versions of the given context
+    ///
+    /// The context is the [`Versions::context`](crate::Versions::context) of a previous read. The
+    /// new version descends from all the versions returned by the read, so writing the merged
+    /// value of the siblings resolves the conflict.
+    ///
+    /// The value is sent to all the replicas at once. The call returns as soon as enough replicas
+    /// acknowledged the write, the remaining replicas are still written in the background.
+    ///
+    /// A replica which can't be connected to gets a hint instead, which is delivered by
+    /// [`KvStore::replay_hints`] once the replica is back. The hint doesn't count as an
+    /// acknowledgement of the write, unless the [`sloppy_quorum`](crate::Config::sloppy_quorum)
+    /// is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to store the value for
+    /// * `value` - The value to store
+    /// * `context` - The clock of the versions the value replaces
+    /// * `consistency` - The number of replicas which have to acknowledge the write
+    pub async fn put_with_context(&self, key: &[u8], value: Vec<u8>, context: &VectorClock, consistency: Consistency)
+        -> Result<(), ServiceError> {
+        let version = Versioned::new(value, self.next_clock(context));
+        self.write(key, version, consistency).await
+    }
+
+    /// Send the version of the key to all its replicas, the write to a replica which can't be
+    /// connected to is kept as a hint
+    async fn write(&self, key: &[u8], version: Versioned, consistency: Consistency) -> Result<(), ServiceError> {
+        let replicas = self.replicas(key).await?;
+        let key = key.to_vec();
+        let sloppy = self.node.config().sloppy_quorum;
+        let local = || self.put_local(key.clone(), version.clone());
+        self.quorum(replicas, consistency, local, |replica, client| {
+            let (key, version, hints) = (key.clone(), version.clone(), self.hints.clone());
+            async move {
+                match client.put(key.clone(), version.clone()).await {
+                    Err(err @ ClientError::ConnectionFailed(_)) if hints.push(replica.clone(), key, version) => {
+                        log::debug!("Replica {} is unreachable, the write is kept as a hint", replica.id());
+                        if sloppy { Ok(()) } else { Err(err) }
+                    }
+                    result => result,
+                }
+            }
+        }).await?;
+
+        Ok(())
+    }
+
+    /// Get the versions of the key from the first replica which answers
+    ///
+    /// It's the same as [`KvStore::get_with`] with [`Consistency::One`].
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to get the versions for
+    pub async fn get(&self, key: &[u8]) -> Result<Versions, ServiceError> {
+        self.get_with(key, Consistency::One).await
+    }
+
+    /// Get the versions of the key from its replicas
+    ///
+    /// All the replicas are asked at once and the call returns as soon as enough of them
+    /// answered. The versions of all the answers are merged, so the outdated versions are
+    /// dropped and the concurrent ones are returned as siblings.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to get the versions for
+    /// * `consistency` - The number of replicas which have to answer
+    pub async fn get_with(&self, key: &[u8], consistency: Consistency) -> Result<Versions, ServiceError> {
+        let replicas = self.replicas(key).await?;
+        let key = key.to_vec();
+        let local = || self.get_local(&key);
+        let responses = self.quorum(replicas, consistency, local, |_, client| {
+            let key = key.clone();
+            async move { client.get(key).await }
+        }).await?;
+
+        let mut siblings = Vec::new();
+        for version in responses.into_iter().flat_map(|(_, versions)| versions) {
+            Versioned::reconcile(&mut siblings, version);
+        }
+
+        Ok(Versions::new(siblings))
+    }
+
+    /// Delete the key from all its replicas and wait for all of them
+    ///
+    /// It's the same as [`KvStore::delete_with`] with [`Consistency::All`].
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to delete
+    pub async fn delete(&self, key: &[u8]) -> Result<(), ServiceError> {
+        self.delete_with(key, Consistency::All).await
+    }
+
+    /// Delete the versions of the key known to its replicas
+    ///
+    /// The key is read with the same consistency first, so the delete replaces all the versions
+    /// returned by the read, see [`KvStore::delete_with_context`].
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to delete
+    /// * `consistency` - The number of replicas which have to answer the read and acknowledge the
+    ///   delete
+    pub async fn delete_with(&self, key: &[u8], consistency: Consistency) -> Result<(), ServiceError> {
+        let context = self.get_with(key, consistency).await?.context();
+        self.delete_with_context(key, &context, consistency).await
+    }
+
+    /// Delete the versions of the given context from all the replicas of the key
+    ///
+    /// The delete is written like a value, as a tombstone descending from the versions of the
+    /// context, see [`KvStore::put_with_context`]. A version written concurrently is kept as a
+    /// sibling of the tombstone.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to delete
+    /// * `context` - The clock of the versions to delete
+    /// * `consistency` - The number of replicas which have to acknowledge the delete
+    pub async fn delete_with_context(&self, key: &[u8], context: &VectorClock, consistency: Consistency)
+        -> Result<(), ServiceError> {
+        let version = Versioned::tombstone(self.next_clock(context));
+        self.write(key, version, consistency).await
+    }
+
+    /// Send the request to all the replicas and wait until enough of them answer.
+    ///
+    /// The current node is served by the `local` function, the other replicas get the `request`.
+    /// A failure of the local storage counts as a failed replica.
+    /// Returns the answers together with the index of the replica, ordered by the index. The
+    /// requests still in flight keep running in the background.
+    async fn quorum<T, L, F, Fut>(&self, replicas: Vec<Node>, consistency: Consistency, local: L, request: F)
+        -> Result<Vec<(usize, T)>, ServiceError>
+        where T: Send + 'static,
+              L: FnOnce() -> Result<T, StorageError>,
+              F: Fn(Node, C) -> Fut,
+              Fut: Future<Output = Result<T, ClientError>> + Send + 'static {
+        let node = self.node.node();
+        let total = replicas.len();
+        let required = consistency.required(total);
+
+        let mut responses = Vec::with_capacity(total);
+        let mut failures = 0;
+        let mut tasks = JoinSet::new();
+        let mut local = Some(local);
+        for (index, replica) in replicas.into_iter().enumerate() {
+            if replica == node {
+                match local.take().map(|local| local()) {
+                    Some(Ok(response)) => responses.push((index, response)),
+                    Some(Err(err)) => {
+                        log::warn!("Local replica failed: {}", err);
+                        failures += 1;
+                    }
+                    None => {}
+                }
+                continue;
+            }
+
+            let client: C = replica.client();
+            let request = request(replica, client);
+            tasks.spawn(async move { (index, request.await) });
+        }
+
+        while responses.len() < required && failures <= total - required {
+            match tasks.join_next().await {
+                Some(Ok((index, Ok(response)))) => responses.push((index, response)),
+                Some(Ok((index, Err(err)))) => {
+                    log::debug!("Replica {} failed: {}", index, err);
+                    failures += 1;
+                }
+                Some(Err(err)) => {
+                    log::debug!("Request to a replica failed: {}", err);
+                    failures += 1;
+                }
+                None => break,
+            }
+        }
+
+        // The replicas which didn't answer yet are still written, or get their hints, even if the
+        // quorum is not reached
+        tasks.detach_all();
+        if responses.len() < required {
+            return Err(ServiceError::QuorumNotReached(required, responses.len()));
+        }
+
+        responses.sort_by_key(|(index, _)| *index);
+
+        Ok(responses)
+    }
+}
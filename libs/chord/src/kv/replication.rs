@@ -0,0 +1,108 @@
+use std::sync::PoisonError;
+use crate::{Client, KvStore, Node};
+use crate::kv::{Entry, StorageError, TRANSFER_BATCH_SIZE};
+use crate::service::error::ServiceError;
+
+/// The replicas of the keys owned by the node, as of the last synchronization
+#[derive(Clone, PartialEq)]
+pub(crate) struct ReplicaSet {
+    predecessor: Node,
+    replicas: Vec<Node>,
+}
+
+impl<C: Client> KvStore<C> {
+    /// Get the nodes storing a copy of the given key, starting with the successor of the key.
+    ///
+    /// The successor is followed by the next nodes of its successor list, skipping the nodes
+    /// running on a physical host which already has a copy. The list contains at most
+    /// [`replication_factor`](crate::Config::replication_factor) nodes.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to get the replicas for
+    pub async fn replicas(&self, key: &[u8]) -> Result<Vec<Node>, ServiceError> {
+        let owner = self.node.find_successor(self.key_id(key)).await?;
+        let factor = self.node.config().replication_factor;
+        if factor <= 1 {
+            return Ok(vec![owner]);
+        }
+
+        let successors = if owner == self.node.node() {
+            self.node.successor_list()
+        } else {
+            let client: C = owner.client();
+            client.successor_list().await?
+        };
+
+        Ok(Self::replica_set(owner, successors, factor))
+    }
+
+    /// Store copies of the keys the current node is a replica of
+    ///
+    /// It's called by the RPC server on an incoming `replicate` request.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - The keys with their versions
+    pub fn replicate_local(&self, entries: Vec<Entry>) -> Result<(), StorageError> {
+        let mut data = self.write_data();
+        for (key, versions) in entries {
+            self.merge_versions(data.as_mut(), key, versions)?;
+        }
+
+        Ok(())
+    }
+
+    /// Re-establish the replicas of the keys owned by the current node.
+    ///
+    /// The keys in the range `(predecessor, self]` are copied to the replicas when the predecessor
+    /// or the replicas changed since the last call, e.g. after `notify` or `stabilize` updated
+    /// them. Nothing is done while the predecessor is unknown.
+    ///
+    /// > **Note**
+    /// >
+    /// > This method should be called periodically, see [`Maintenance`](crate::Maintenance).
+    pub async fn sync_replicas(&self) -> Result<(), ServiceError> {
+        let factor = self.node.config().replication_factor;
+        let predecessor = match self.node.predecessor() {
+            Some(predecessor) if factor > 1 => predecessor,
+            _ => return Ok(()),
+        };
+
+        let node = self.node.node();
+        let mut replicas = Self::replica_set(node.clone(), self.node.successor_list(), factor);
+        replicas.remove(0);
+        let current = ReplicaSet { predecessor, replicas };
+        if self.replication.lock().unwrap_or_else(PoisonError::into_inner).as_ref() == Some(&current) {
+            return Ok(());
+        }
+
+        let entries = self.entries_in_range(current.predecessor.id(), node.id())?;
+        for replica in &current.replicas {
+            let client: C = replica.client();
+            for batch in entries.chunks(TRANSFER_BATCH_SIZE) {
+                client.replicate(batch.to_vec()).await?;
+            }
+        }
+
+        log::debug!("Node {} replicated {} keys to {} nodes", node.id(), entries.len(), current.replicas.len());
+        *self.replication.lock().unwrap_or_else(PoisonError::into_inner) = Some(current);
+
+        Ok(())
+    }
+
+    pub(super) fn replica_set(owner: Node, successors: Vec<Node>, factor: usize) -> Vec<Node> {
+        let mut replicas = vec![owner];
+        for successor in successors {
+            if replicas.len() >= factor {
+                break;
+            }
+
+            if !replicas.iter().any(|replica| replica.is_same_host(&successor)) {
+                replicas.push(successor);
+            }
+        }
+
+        replicas
+    }
+}
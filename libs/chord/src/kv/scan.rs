@@ -0,0 +1,131 @@
+use crate::{Client, KvStore, Node};
+use crate::kv::{Entry, StorageError, Versioned, TRANSFER_BATCH_SIZE};
+use crate::service::error::ServiceError;
+
+/// Position of a scan after the last key of a page
+///
+/// It's only valid for the range of the scan which returned it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScanToken {
+    key: Vec<u8>,
+}
+
+impl ScanToken {
+    /// Create a token continuing the scan after the given key
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The last key of the previous page
+    pub fn new(key: Vec<u8>) -> Self {
+        Self { key }
+    }
+
+    /// Get the last key of the previous page
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+}
+
+/// A page of the keys returned by [`KvStore::scan`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScanPage {
+    /// The keys of the page, in the order of the ring
+    pub entries: Vec<Entry>,
+    /// The token of the next page, `None` once the whole range was scanned
+    pub next: Option<ScanToken>,
+}
+
+impl<C: Client> KvStore<C> {
+    /// Get the keys whose ids are in the range `(from, to]` page by page, in the order of the ring
+    ///
+    /// The range wraps around the ring when `from >= to`, so it's the whole ring when
+    /// `from == to`. The keys are read from the successor of the start of the range and then from
+    /// the following nodes, until the end of the range or the limit is reached. The next page is
+    /// requested with the [`ScanToken`] of the previous one, the last page may be empty. The keys
+    /// whose versions are all tombstones were deleted and they're skipped.
+    ///
+    /// > **Note**
+    /// >
+    /// > Every key is read from the node owning it only and the pages are not an atomic snapshot.
+    /// > Keys written or moved to another node while the range is being scanned may be missed.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The exclusive start of the range
+    /// * `to` - The inclusive end of the range
+    /// * `limit` - The maximum number of keys of the page
+    /// * `token` - The token of the previous page, `None` for the first page
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ServiceError::InvalidArgument`] if `limit` is 0.
+    pub async fn scan(&self, from: u64, to: u64, limit: usize, token: Option<&ScanToken>)
+        -> Result<ScanPage, ServiceError> {
+        if limit == 0 {
+            return Err(ServiceError::InvalidArgument("The limit of a scan must be positive".to_string()));
+        }
+        let ring = &self.node.config().ring;
+        let to = ring.mask(to);
+        // The keys of the range `(position, to]` are left, except the ones up to `after`
+        let (mut position, mut after) = match token {
+            Some(token) => (ring.mask(self.key_id(&token.key).wrapping_sub(1)), Some(token.key.clone())),
+            None => (ring.mask(from), None),
+        };
+
+        let mut entries: Vec<Entry> = Vec::new();
+        loop {
+            let owner = self.node.find_successor(ring.mask(position.wrapping_add(1))).await?;
+            let end = if Node::is_between_on_ring(owner.id(), position, to) { owner.id() } else { to };
+
+            loop {
+                let remaining = limit - entries.len();
+                if remaining == 0 {
+                    let next = entries.last().map(|(key, _)| ScanToken::new(key.clone()));
+                    return Ok(ScanPage { entries, next });
+                }
+
+                let count = remaining.min(TRANSFER_BATCH_SIZE);
+                let batch = self.scan_node(&owner, position, end, after.take(), count).await?;
+                let exhausted = batch.len() < count;
+                after = batch.last().map(|(key, _)| key.clone());
+                entries.extend(batch.into_iter().filter(|(_, versions)| !versions.iter().all(Versioned::is_tombstone)));
+                if exhausted {
+                    break;
+                }
+            }
+
+            if end == to {
+                return Ok(ScanPage { entries, next: None });
+            }
+            position = end;
+            after = None;
+        }
+    }
+
+    /// Get the next page of the keys stored on the current node whose ids are in the range
+    /// `(from, to]`, in the order of the ring
+    ///
+    /// It's called by the RPC server on an incoming `scan` request.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The exclusive start of the range
+    /// * `to` - The inclusive end of the range
+    /// * `after` - The last key of the previous page, `None` for the first page
+    /// * `limit` - The maximum number of keys to return
+    pub fn scan_local(&self, from: u64, to: u64, after: Option<&[u8]>, limit: usize) -> Result<Vec<Entry>, StorageError> {
+        let after = after.map(|key| (self.key_id(key), key));
+        self.read_data().range(from, to, after, limit)
+    }
+
+    /// Get the next page of the keys of the range `(from, to]` from the given node
+    async fn scan_node(&self, node: &Node, from: u64, to: u64, after: Option<Vec<u8>>, limit: usize)
+        -> Result<Vec<Entry>, ServiceError> {
+        if *node == self.node.node() {
+            return Ok(self.scan_local(from, to, after.as_deref(), limit)?);
+        }
+
+        let client: C = node.client();
+        Ok(client.scan(from, to, after, limit).await?)
+    }
+}
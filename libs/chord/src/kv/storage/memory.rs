@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use crate::kv::{Entry, Versioned};
+use crate::kv::storage::{id_intervals, StorageBackend, StorageError};
+
+/// Storage keeping all the keys in memory
+///
+/// The keys are lost when the process exits.
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    data: BTreeMap<(u64, Vec<u8>), Vec<Versioned>>,
+}
+
+impl MemoryBackend {
+    /// Create an empty in-memory storage
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Iterate over all the keys with their ids, in the order of the ids
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (u64, &[u8], &[Versioned])> {
+        self.data.iter().map(|((id, key), versions)| (*id, key.as_slice(), versions.as_slice()))
+    }
+}
+
+impl StorageBackend for MemoryBackend {
+    fn get(&self, id: u64, key: &[u8]) -> Result<Option<Vec<Versioned>>, StorageError> {
+        Ok(self.data.get(&(id, key.to_vec())).cloned())
+    }
+
+    fn put(&mut self, id: u64, key: Vec<u8>, versions: Vec<Versioned>) -> Result<(), StorageError> {
+        self.data.insert((id, key), versions);
+        Ok(())
+    }
+
+    fn delete(&mut self, id: u64, key: &[u8]) -> Result<(), StorageError> {
+        self.data.remove(&(id, key.to_vec()));
+        Ok(())
+    }
+
+    fn range(&self, from: u64, to: u64, after: Option<(u64, &[u8])>, limit: usize) -> Result<Vec<Entry>, StorageError> {
+        let intervals = id_intervals(from, to);
+        // The intervals before the one of the last returned key were returned already
+        let first = after
+            .and_then(|(id, _)| intervals.iter().position(|ids| ids.contains(&id)))
+            .unwrap_or(0);
+
+        let mut entries = Vec::new();
+        for (index, ids) in intervals.into_iter().enumerate().skip(first) {
+            let start = match after {
+                Some((id, key)) if index == first => Bound::Excluded((id, key.to_vec())),
+                _ => Bound::Included((*ids.start(), Vec::new())),
+            };
+            let end = match ids.end().checked_add(1) {
+                Some(next) => Bound::Excluded((next, Vec::new())),
+                None => Bound::Unbounded,
+            };
+
+            let remaining = limit - entries.len();
+            entries.extend(self.data.range((start, end))
+                .take(remaining)
+                .map(|((_, key), versions)| (key.clone(), versions.clone())));
+            if entries.len() >= limit {
+                break;
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::VectorClock;
+    use super::*;
+
+    fn backend(ids: &[u64]) -> MemoryBackend {
+        let mut backend = MemoryBackend::new();
+        for id in ids {
+            let version = Versioned::new(b"value".to_vec(), VectorClock::new());
+            backend.put(*id, id.to_string().into_bytes(), vec![version]).unwrap();
+        }
+
+        backend
+    }
+
+    fn keys(entries: Vec<Entry>) -> Vec<String> {
+        entries.into_iter().map(|(key, _)| String::from_utf8(key).unwrap()).collect()
+    }
+
+    #[test]
+    fn it_should_store_keys() {
+        let mut backend = backend(&[4]);
+
+        assert_eq!(backend.get(4, b"4").unwrap().unwrap().len(), 1);
+        assert_eq!(backend.get(4, b"other").unwrap(), None);
+        assert_eq!(backend.len(), 1);
+
+        backend.delete(4, b"4").unwrap();
+        assert_eq!(backend.get(4, b"4").unwrap(), None);
+        assert!(backend.is_empty());
+    }
+
+    #[test]
+    fn it_should_iterate_range_in_ring_order() {
+        let backend = backend(&[0, 4, 10, 20, 30, u64::MAX]);
+
+        assert_eq!(keys(backend.range(4, 20, None, 10).unwrap()), vec!["10", "20"]);
+        assert_eq!(keys(backend.range(20, 4, None, 10).unwrap()), vec!["30", &u64::MAX.to_string(), "0", "4"]);
+        assert_eq!(keys(backend.range(10, 10, None, 10).unwrap()).len(), 6);
+        assert!(backend.range(u64::MAX, 0, None, 10).unwrap().len() == 1);
+    }
+
+    #[test]
+    fn it_should_iterate_range_in_batches() {
+        let backend = backend(&[0, 4, 10, 20, 30]);
+
+        let first = keys(backend.range(20, 10, None, 2).unwrap());
+        assert_eq!(first, vec!["30", "0"]);
+        let second = keys(backend.range(20, 10, Some((0, b"0")), 2).unwrap());
+        assert_eq!(second, vec!["4", "10"]);
+        assert!(backend.range(20, 10, Some((10, b"10")), 2).unwrap().is_empty());
+    }
+}
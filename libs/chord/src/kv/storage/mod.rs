@@ -0,0 +1,120 @@
+mod memory;
+mod wal;
+
+pub use memory::MemoryBackend;
+pub use wal::WalBackend;
+
+use std::error::Error;
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+use crate::kv::{Entry, Versioned};
+
+/// Storage of the keys of a [`KvStore`](crate::KvStore)
+///
+/// Every key is stored together with its id on the ring, and the keys are ordered by their ids,
+/// so the keys of an interval of the ring can be read without scanning all of them. Keys with the
+/// same id are ordered by their bytes.
+///
+/// The store serializes the access to the backend, so the backend doesn't have to be safe for
+/// concurrent writes.
+pub trait StorageBackend: Send + Sync {
+    /// Get all the versions of the key
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the key on the ring
+    /// * `key` - The key to get the versions for
+    fn get(&self, id: u64, key: &[u8]) -> Result<Option<Vec<Versioned>>, StorageError>;
+
+    /// Store the versions of the key, replacing the previous ones
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the key on the ring
+    /// * `key` - The key to store the versions for
+    /// * `versions` - All the versions of the key
+    fn put(&mut self, id: u64, key: Vec<u8>, versions: Vec<Versioned>) -> Result<(), StorageError>;
+
+    /// Delete the key
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the key on the ring
+    /// * `key` - The key to delete
+    fn delete(&mut self, id: u64, key: &[u8]) -> Result<(), StorageError>;
+
+    /// Get the keys whose ids are in the range `(from, to]`, in the order of the ring
+    ///
+    /// The range wraps around the ring when `from >= to`, so it's the whole ring when
+    /// `from == to`.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The exclusive start of the range
+    /// * `to` - The inclusive end of the range
+    /// * `after` - The id and the key of the last entry of the previous batch, `None` for the
+    ///   first batch
+    /// * `limit` - The maximum number of keys to return
+    fn range(&self, from: u64, to: u64, after: Option<(u64, &[u8])>, limit: usize) -> Result<Vec<Entry>, StorageError>;
+
+    /// Get the number of stored keys
+    fn len(&self) -> usize;
+
+    /// Returns true if no key is stored
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Error returned by a [`StorageBackend`]
+#[derive(Debug)]
+pub enum StorageError {
+    /// Reading or writing the underlying file failed
+    Io(std::io::Error),
+    /// The stored data can't be decoded
+    Corrupted(String),
+    /// A write failed and the storage couldn't be restored to its last consistent state, so it
+    /// doesn't accept writes anymore
+    Failed,
+}
+
+impl From<std::io::Error> for StorageError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Storage I/O failed: {}", err),
+            Self::Corrupted(message) => write!(f, "Storage is corrupted: {}", message),
+            Self::Failed => write!(f, "Storage failed after an earlier write error"),
+        }
+    }
+}
+
+impl Error for StorageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Corrupted(_) | Self::Failed => None,
+        }
+    }
+}
+
+/// Split the range `(from, to]` of the ring into the id intervals which don't wrap around, in the
+/// order of the ring
+pub(crate) fn id_intervals(from: u64, to: u64) -> Vec<RangeInclusive<u64>> {
+    if from < to {
+        return vec![from + 1..=to];
+    }
+
+    let mut intervals = Vec::with_capacity(2);
+    if from < u64::MAX {
+        intervals.push(from + 1..=u64::MAX);
+    }
+    intervals.push(0..=to);
+
+    intervals
+}
@@ -0,0 +1,543 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use crate::VectorClock;
+use crate::kv::{Entry, Versioned};
+use crate::kv::storage::{MemoryBackend, StorageBackend, StorageError};
+
+/// Minimum number of records in the log before it's compacted
+const COMPACTION_THRESHOLD: usize = 1024;
+
+/// Size of the header of a record, the length and the checksum of the payload
+const HEADER_SIZE: usize = 12;
+
+const PUT: u8 = 0;
+const DELETE: u8 = 1;
+
+/// Length written instead of the value of a tombstone
+const TOMBSTONE: u32 = u32::MAX;
+
+/// Storage keeping the keys in memory and appending every change to a write-ahead log
+///
+/// Every write is synced to disk before it's applied, so a write which returned is never lost.
+/// On [`WalBackend::open`] the log is replayed to restore the keys. A record which was only
+/// partially written when the process crashed is dropped.
+///
+/// A write which fails is cut off the log, so the following records stay readable. When the log
+/// can't be cut back either, every further write fails with [`StorageError::Failed`] until the
+/// log is compacted or opened again.
+///
+/// The log is rewritten with only the current versions of the keys once it holds more than twice
+/// as many records as there are keys, see [`WalBackend::compact`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn run<C: chord_rs::Client>(node: chord_rs::NodeHandle<C>) {
+/// use chord_rs::{KvStore, WalBackend};
+///
+/// let backend = WalBackend::open("/var/lib/chord/keys.wal").unwrap();
+/// let store = KvStore::with_backend(node, backend);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct WalBackend {
+    path: PathBuf,
+    log: File,
+    /// The length of the log up to the end of the last complete record
+    len: u64,
+    records: usize,
+    memory: MemoryBackend,
+    failed: bool,
+    #[cfg(test)]
+    fail_reopen: bool,
+}
+
+impl WalBackend {
+    /// Open the log at the given path and restore the keys written to it, or create an empty log
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the log file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::Corrupted`] if a record other than the last one can't be decoded,
+    /// e.g. because its length is corrupted.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        let path = path.as_ref().to_path_buf();
+        let mut log = OpenOptions::new().create(true).read(true).append(true).open(&path)?;
+        let mut bytes = Vec::new();
+        log.read_to_end(&mut bytes)?;
+
+        let mut memory = MemoryBackend::new();
+        let mut records = 0;
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let payload = match read_record(&bytes[offset..]) {
+                Some(payload) => payload,
+                // A torn write of the last record, the write never returned
+                None if is_tail(&bytes[offset..]) => break,
+                None => return Err(StorageError::Corrupted(format!("Invalid record at offset {}", offset))),
+            };
+
+            apply(&mut memory, payload)?;
+            offset += HEADER_SIZE + payload.len();
+            records += 1;
+        }
+
+        if offset < bytes.len() {
+            log::warn!("Dropping {} bytes of a partially written record of {}", bytes.len() - offset, path.display());
+            log.set_len(offset as u64)?;
+            log.sync_all()?;
+        }
+
+        Ok(Self {
+            path,
+            log,
+            len: offset as u64,
+            records,
+            memory,
+            failed: false,
+            #[cfg(test)]
+            fail_reopen: false,
+        })
+    }
+
+    /// Get the path of the log file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Rewrite the log with only the current versions of the keys.
+    ///
+    /// The new log is written next to the current one and it replaces it once it's synced to
+    /// disk, so a crash during the compaction leaves the current log intact.
+    ///
+    /// When the compaction fails after the new log replaced the current one, every further write
+    /// fails with [`StorageError::Failed`] until the log is compacted or opened again.
+    pub fn compact(&mut self) -> Result<(), StorageError> {
+        let compacted = self.path.with_extension("compact");
+        let mut file = File::create(&compacted)?;
+        let mut len = 0;
+        for (id, key, versions) in self.memory.iter() {
+            let record = record(&encode_put(id, key, versions));
+            file.write_all(&record)?;
+            len += record.len() as u64;
+        }
+        file.sync_all()?;
+
+        // The current log is unlinked by the rename, so nothing may be appended to it anymore
+        self.failed = true;
+        std::fs::rename(&compacted, &self.path)?;
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            File::open(dir)?.sync_all()?;
+        }
+
+        self.log = self.reopen()?;
+        self.len = len;
+        self.records = self.memory.len();
+        self.failed = false;
+        log::debug!("Compacted {} to {} records", self.path.display(), self.records);
+
+        Ok(())
+    }
+
+    fn reopen(&self) -> std::io::Result<File> {
+        #[cfg(test)]
+        if self.fail_reopen {
+            return Err(std::io::Error::other("Reopening the log failed"));
+        }
+
+        OpenOptions::new().read(true).append(true).open(&self.path)
+    }
+
+    fn append(&mut self, payload: Vec<u8>) -> Result<(), StorageError> {
+        if self.failed {
+            return Err(StorageError::Failed);
+        }
+
+        let record = record(&payload);
+        if let Err(err) = self.log.write_all(&record).and_then(|()| self.log.sync_data()) {
+            // The records appended after a partially written one couldn't be read anymore
+            if let Err(truncate_err) = self.log.set_len(self.len).and_then(|()| self.log.sync_all()) {
+                log::error!("Cutting off the failed write of {} failed: {}", self.path.display(), truncate_err);
+                self.failed = true;
+            }
+            return Err(err.into());
+        }
+
+        self.len += record.len() as u64;
+        self.records += 1;
+
+        Ok(())
+    }
+
+    /// Compact the log once it's large enough. The write which triggered the compaction is
+    /// already durable, so a failed compaction is only logged, see [`WalBackend::compact`].
+    fn compact_if_needed(&mut self) {
+        if self.records > COMPACTION_THRESHOLD && self.records > 2 * self.memory.len() {
+            if let Err(err) = self.compact() {
+                log::error!("Compacting {} failed: {}", self.path.display(), err);
+            }
+        }
+    }
+}
+
+impl StorageBackend for WalBackend {
+    fn get(&self, id: u64, key: &[u8]) -> Result<Option<Vec<Versioned>>, StorageError> {
+        self.memory.get(id, key)
+    }
+
+    fn put(&mut self, id: u64, key: Vec<u8>, versions: Vec<Versioned>) -> Result<(), StorageError> {
+        self.append(encode_put(id, &key, &versions))?;
+        self.memory.put(id, key, versions)?;
+        self.compact_if_needed();
+        Ok(())
+    }
+
+    fn delete(&mut self, id: u64, key: &[u8]) -> Result<(), StorageError> {
+        if self.memory.get(id, key)?.is_none() {
+            return Ok(());
+        }
+
+        self.append(encode_delete(id, key))?;
+        self.memory.delete(id, key)?;
+        self.compact_if_needed();
+        Ok(())
+    }
+
+    fn range(&self, from: u64, to: u64, after: Option<(u64, &[u8])>, limit: usize) -> Result<Vec<Entry>, StorageError> {
+        self.memory.range(from, to, after, limit)
+    }
+
+    fn len(&self) -> usize {
+        self.memory.len()
+    }
+}
+
+/// Frame the payload with its length and checksum
+fn record(payload: &[u8]) -> Vec<u8> {
+    let mut record = Vec::with_capacity(HEADER_SIZE + payload.len());
+    record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    record.extend_from_slice(&seahash::hash(payload).to_le_bytes());
+    record.extend_from_slice(payload);
+    record
+}
+
+/// Get the payload of the record at the start of the bytes, if it's complete and intact
+fn read_record(bytes: &[u8]) -> Option<&[u8]> {
+    let len = u32::from_le_bytes(bytes.get(..4)?.try_into().ok()?) as usize;
+    let checksum = u64::from_le_bytes(bytes.get(4..HEADER_SIZE)?.try_into().ok()?);
+    let payload = bytes.get(HEADER_SIZE..HEADER_SIZE + len)?;
+
+    (seahash::hash(payload) == checksum).then_some(payload)
+}
+
+/// Returns true if the invalid record at the start of the bytes is the last one of the log, i.e.
+/// a write cut off by a crash
+///
+/// The record has to run up to the end of the log, and no intact record may follow it, otherwise
+/// its length was corrupted and the records after it would be lost.
+fn is_tail(bytes: &[u8]) -> bool {
+    let runs_to_end = match bytes.get(..4) {
+        Some(len) => HEADER_SIZE + u32::from_le_bytes(len.try_into().unwrap()) as usize >= bytes.len(),
+        None => true,
+    };
+
+    runs_to_end && (1..bytes.len()).all(|offset| read_record(&bytes[offset..]).is_none())
+}
+
+fn encode_put(id: u64, key: &[u8], versions: &[Versioned]) -> Vec<u8> {
+    let mut payload = vec![PUT];
+    payload.extend_from_slice(&id.to_le_bytes());
+    write_bytes(&mut payload, key);
+    payload.extend_from_slice(&(versions.len() as u32).to_le_bytes());
+    for version in versions {
+        match &version.value {
+            Some(value) => write_bytes(&mut payload, value),
+            None => payload.extend_from_slice(&TOMBSTONE.to_le_bytes()),
+        }
+        let counters = version.clock.counters();
+        payload.extend_from_slice(&(counters.len() as u32).to_le_bytes());
+        for (node_id, counter) in counters {
+            payload.extend_from_slice(&node_id.to_le_bytes());
+            payload.extend_from_slice(&counter.to_le_bytes());
+        }
+    }
+
+    payload
+}
+
+fn encode_delete(id: u64, key: &[u8]) -> Vec<u8> {
+    let mut payload = vec![DELETE];
+    payload.extend_from_slice(&id.to_le_bytes());
+    write_bytes(&mut payload, key);
+    payload
+}
+
+fn write_bytes(payload: &mut Vec<u8>, bytes: &[u8]) {
+    payload.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    payload.extend_from_slice(bytes);
+}
+
+/// Apply the change of the record to the keys
+fn apply(memory: &mut MemoryBackend, payload: &[u8]) -> Result<(), StorageError> {
+    let mut reader = Reader { bytes: payload };
+    let tag = reader.u8()?;
+    let id = reader.u64()?;
+    let key = reader.bytes()?;
+    match tag {
+        PUT => {
+            let mut versions = Vec::new();
+            for _ in 0..reader.u32()? {
+                let value = reader.optional_bytes()?;
+                let mut clock = VectorClock::new();
+                for _ in 0..reader.u32()? {
+                    let (node_id, counter) = (reader.u64()?, reader.u64()?);
+                    clock.set(node_id, counter);
+                }
+                versions.push(Versioned { value, clock });
+            }
+            memory.put(id, key, versions)
+        }
+        DELETE => memory.delete(id, &key),
+        tag => Err(StorageError::Corrupted(format!("Unknown record type {}", tag))),
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], StorageError> {
+        if self.bytes.len() < len {
+            return Err(StorageError::Corrupted("Record is too short".to_string()));
+        }
+
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, StorageError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, StorageError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, StorageError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, StorageError> {
+        let len = self.u32()? as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    /// Read the value of a version, `None` for a tombstone
+    fn optional_bytes(&mut self) -> Result<Option<Vec<u8>>, StorageError> {
+        match self.u32()? {
+            TOMBSTONE => Ok(None),
+            len => Ok(Some(self.take(len as usize)?.to_vec())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::OpenOptions;
+    use super::*;
+
+    /// Path of a log in the temporary directory, removed when it's dropped
+    struct TempLog(PathBuf);
+
+    impl TempLog {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("chord-wal-{}-{}.wal", std::process::id(), name));
+            let _ = std::fs::remove_file(&path);
+            Self(path)
+        }
+    }
+
+    impl Drop for TempLog {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn version(value: &[u8], node_id: u64) -> Versioned {
+        let mut clock = VectorClock::new();
+        clock.increment(node_id);
+        Versioned::new(value.to_vec(), clock)
+    }
+
+    #[test]
+    fn it_should_restore_keys_from_log() {
+        let log = TempLog::new("restore");
+
+        let mut backend = WalBackend::open(&log.0).unwrap();
+        backend.put(4, b"4".to_vec(), vec![version(b"a", 1), version(b"b", 2)]).unwrap();
+        backend.put(10, b"10".to_vec(), vec![version(b"c", 1)]).unwrap();
+        backend.put(20, b"20".to_vec(), vec![version(b"d", 1)]).unwrap();
+        backend.delete(10, b"10").unwrap();
+        let tombstone = Versioned::tombstone(version(b"", 1).clock);
+        backend.put(30, b"30".to_vec(), vec![tombstone.clone(), version(b"", 2)]).unwrap();
+        drop(backend);
+
+        let backend = WalBackend::open(&log.0).unwrap();
+        assert_eq!(backend.len(), 3);
+        assert_eq!(backend.get(4, b"4").unwrap(), Some(vec![version(b"a", 1), version(b"b", 2)]));
+        assert_eq!(backend.get(10, b"10").unwrap(), None);
+        assert_eq!(backend.get(30, b"30").unwrap(), Some(vec![tombstone, version(b"", 2)]));
+        assert_eq!(backend.range(4, 20, None, 10).unwrap(), vec![(b"20".to_vec(), vec![version(b"d", 1)])]);
+    }
+
+    #[test]
+    fn it_should_drop_partially_written_record() {
+        let log = TempLog::new("torn");
+
+        let mut backend = WalBackend::open(&log.0).unwrap();
+        backend.put(4, b"4".to_vec(), vec![version(b"a", 1)]).unwrap();
+        drop(backend);
+
+        // The process crashed in the middle of the next write
+        let torn = record(&encode_put(10, b"10", &[version(b"b", 1)]));
+        let mut file = OpenOptions::new().append(true).open(&log.0).unwrap();
+        file.write_all(&torn[..torn.len() - 3]).unwrap();
+        drop(file);
+
+        let mut backend = WalBackend::open(&log.0).unwrap();
+        assert_eq!(backend.len(), 1);
+        backend.put(20, b"20".to_vec(), vec![version(b"c", 1)]).unwrap();
+        drop(backend);
+
+        let backend = WalBackend::open(&log.0).unwrap();
+        assert_eq!(backend.len(), 2);
+        assert!(backend.get(20, b"20").unwrap().is_some());
+    }
+
+    #[test]
+    fn it_should_refuse_writes_when_failed_write_cannot_be_cut_off() {
+        let log = TempLog::new("failed");
+
+        let mut backend = WalBackend::open(&log.0).unwrap();
+        backend.put(4, b"4".to_vec(), vec![version(b"a", 1)]).unwrap();
+
+        // Neither writing nor truncating works on a read-only file
+        let writable = std::mem::replace(&mut backend.log, File::open(&log.0).unwrap());
+        assert!(matches!(backend.put(10, b"10".to_vec(), vec![version(b"b", 1)]), Err(StorageError::Io(_))));
+        backend.log = writable;
+        assert!(matches!(backend.put(20, b"20".to_vec(), vec![version(b"c", 1)]), Err(StorageError::Failed)));
+        assert_eq!(backend.len(), 1);
+
+        // The compaction rewrites the log from the keys in memory
+        backend.compact().unwrap();
+        backend.put(20, b"20".to_vec(), vec![version(b"c", 1)]).unwrap();
+        drop(backend);
+
+        let backend = WalBackend::open(&log.0).unwrap();
+        assert_eq!(backend.len(), 2);
+        assert_eq!(backend.get(10, b"10").unwrap(), None);
+    }
+
+    #[test]
+    fn it_should_refuse_writes_when_compacted_log_cannot_be_reopened() {
+        let log = TempLog::new("reopen");
+
+        let mut backend = WalBackend::open(&log.0).unwrap();
+        backend.put(4, b"4".to_vec(), vec![version(b"a", 1)]).unwrap();
+
+        backend.fail_reopen = true;
+        assert!(matches!(backend.compact(), Err(StorageError::Io(_))));
+        assert!(matches!(backend.put(10, b"10".to_vec(), vec![version(b"b", 1)]), Err(StorageError::Failed)));
+
+        backend.fail_reopen = false;
+        backend.compact().unwrap();
+        backend.put(20, b"20".to_vec(), vec![version(b"c", 1)]).unwrap();
+        drop(backend);
+
+        let backend = WalBackend::open(&log.0).unwrap();
+        assert_eq!(backend.len(), 2);
+        assert_eq!(backend.get(10, b"10").unwrap(), None);
+        assert!(backend.get(20, b"20").unwrap().is_some());
+    }
+
+    #[test]
+    fn it_should_reject_corrupted_log() {
+        let log = TempLog::new("corrupted");
+
+        let mut backend = WalBackend::open(&log.0).unwrap();
+        backend.put(4, b"4".to_vec(), vec![version(b"a", 1)]).unwrap();
+        backend.put(10, b"10".to_vec(), vec![version(b"b", 1)]).unwrap();
+        drop(backend);
+
+        let mut bytes = std::fs::read(&log.0).unwrap();
+        bytes[HEADER_SIZE + 2] ^= 0xff;
+        std::fs::write(&log.0, bytes).unwrap();
+
+        assert!(matches!(WalBackend::open(&log.0), Err(StorageError::Corrupted(_))));
+    }
+
+    #[test]
+    fn it_should_reject_log_with_corrupted_length() {
+        let log = TempLog::new("length");
+
+        let mut backend = WalBackend::open(&log.0).unwrap();
+        for id in [4, 10, 20] {
+            backend.put(id, id.to_string().into_bytes(), vec![version(b"a", 1)]).unwrap();
+        }
+        drop(backend);
+
+        // The first record seems to run up to the end of the log
+        let mut bytes = std::fs::read(&log.0).unwrap();
+        bytes[..4].copy_from_slice(&u32::MAX.to_le_bytes()[..]);
+        std::fs::write(&log.0, &bytes).unwrap();
+
+        assert!(matches!(WalBackend::open(&log.0), Err(StorageError::Corrupted(_))));
+        assert_eq!(std::fs::read(&log.0).unwrap(), bytes);
+    }
+
+    #[test]
+    fn it_should_acknowledge_write_when_compaction_fails() {
+        let log = TempLog::new("compaction-failure");
+
+        let mut backend = WalBackend::open(&log.0).unwrap();
+        backend.fail_reopen = true;
+        for counter in 1..=COMPACTION_THRESHOLD as u64 + 1 {
+            backend.put(4, b"4".to_vec(), vec![version(b"a", counter)]).unwrap();
+        }
+
+        // The compaction triggered by the last write failed
+        assert!(matches!(backend.put(10, b"10".to_vec(), vec![version(b"b", 1)]), Err(StorageError::Failed)));
+        drop(backend);
+
+        let backend = WalBackend::open(&log.0).unwrap();
+        assert_eq!(backend.get(4, b"4").unwrap(), Some(vec![version(b"a", COMPACTION_THRESHOLD as u64 + 1)]));
+    }
+
+    #[test]
+    fn it_should_compact_log() {
+        let log = TempLog::new("compact");
+
+        let mut backend = WalBackend::open(&log.0).unwrap();
+        for counter in 1..=3 {
+            backend.put(4, b"4".to_vec(), vec![version(b"a", counter)]).unwrap();
+        }
+        backend.put(10, b"10".to_vec(), vec![version(b"b", 1)]).unwrap();
+        backend.delete(10, b"10").unwrap();
+        let size = std::fs::metadata(&log.0).unwrap().len();
+
+        backend.compact().unwrap();
+        assert!(std::fs::metadata(&log.0).unwrap().len() < size);
+        backend.put(20, b"20".to_vec(), vec![version(b"c", 1)]).unwrap();
+        drop(backend);
+
+        let backend = WalBackend::open(&log.0).unwrap();
+        assert_eq!(backend.len(), 2);
+        assert_eq!(backend.get(4, b"4").unwrap(), Some(vec![version(b"a", 3)]));
+    }
+}
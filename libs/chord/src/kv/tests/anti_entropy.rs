@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use crate::client::{ClientError, MockClient};
+use crate::{KvStore, ServiceError, VectorClock, Versioned};
+use crate::kv::tests::{replicated_service, value, written_by};
+use crate::service::tests::{self, get_lock, MTX};
+
+type Calls = Arc<Mutex<Vec<(u16, &'static str)>>>;
+
+/// Store of the node 8, owning the range (2, 8] with the replicas 16 and 21
+fn owner() -> KvStore<MockClient> {
+    let service = replicated_service(8, 3);
+    service.store().set_successor(tests::node(16));
+    service.store().update_successor_list(vec![tests::node(21), tests::node(32)]);
+    service.store().set_predecessor(tests::node(2));
+
+    KvStore::new(service)
+}
+
+/// Client of a replica serving the requests from its store, recording every key exchange. The
+/// replica is down when it has no store.
+fn replica(port: u16, store: Option<Arc<KvStore<MockClient>>>, calls: Calls) -> MockClient {
+    let mut client = MockClient::new();
+    let merkle = store.clone();
+    client.expect_merkle_tree()
+        .returning(move |from, to| {
+            match &merkle {
+                Some(store) => Ok(store.merkle_tree_local(from, to).unwrap()),
+                None => Err(ClientError::ConnectionFailed(tests::node(port as u64 - 42000))),
+            }
+        });
+    let (transfer, transfer_calls) = (store.clone(), calls.clone());
+    client.expect_transfer_keys()
+        .returning(move |from, to, after| {
+            transfer_calls.lock().unwrap().push((port, "transfer_keys"));
+            Ok(transfer.as_ref().unwrap().transfer_keys_local(from, to, after.as_deref()).unwrap())
+        });
+    client.expect_replicate()
+        .returning(move |entries| {
+            calls.lock().unwrap().push((port, "replicate"));
+            store.as_ref().unwrap().replicate_local(entries).unwrap();
+            Ok(())
+        });
+    client
+}
+
+fn newer(value: &[u8]) -> Versioned {
+    let mut clock = VectorClock::new();
+    clock.increment(8);
+    clock.increment(16);
+
+    Versioned::new(value.to_vec(), clock)
+}
+
+#[tokio::test]
+async fn anti_entropy_should_exchange_differing_keys() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    let store = owner();
+    store.put_local(b"4".to_vec(), written_by(b"old", 8)).unwrap();
+    store.put_local(b"8".to_vec(), written_by(b"value", 8)).unwrap();
+
+    // The node 16 missed the key "8" and the node 8 missed the keys written while it was down
+    let missed = Arc::new(KvStore::new(replicated_service(16, 3)));
+    missed.put_local(b"4".to_vec(), newer(b"new")).unwrap();
+    missed.put_local(b"6".to_vec(), written_by(b"value", 16)).unwrap();
+    missed.put_local(b"12".to_vec(), written_by(b"not owned", 16)).unwrap();
+
+    // The node 21 has all the writes already
+    let synced = Arc::new(KvStore::new(replicated_service(21, 3)));
+    synced.put_local(b"4".to_vec(), newer(b"new")).unwrap();
+    synced.put_local(b"6".to_vec(), written_by(b"value", 16)).unwrap();
+    synced.put_local(b"8".to_vec(), written_by(b"value", 8)).unwrap();
+
+    let calls: Calls = Arc::new(Mutex::new(Vec::new()));
+    let replicas = HashMap::from([(42016, missed.clone()), (42021, synced.clone())]);
+    let recorded = calls.clone();
+    ctx.expect().returning(move |addr, _| {
+        replica(addr.port(), replicas.get(&addr.port()).cloned(), recorded.clone())
+    });
+
+    store.anti_entropy().await.unwrap();
+
+    assert_eq!(value(store.get_local(b"4").unwrap()), Some(b"new".to_vec()));
+    assert_eq!(value(store.get_local(b"6").unwrap()), Some(b"value".to_vec()));
+    assert!(store.get_local(b"12").unwrap().is_empty());
+    assert_eq!(value(missed.get_local(b"8").unwrap()), Some(b"value".to_vec()));
+    assert_eq!(store.merkle_tree_local(2, 8).unwrap(), missed.merkle_tree_local(2, 8).unwrap());
+
+    // The keys are only exchanged with the replica which differs
+    assert!(calls.lock().unwrap().iter().all(|(port, _)| *port == 42016));
+    assert_eq!(synced.len(), 3);
+
+    // Nothing differs anymore
+    calls.lock().unwrap().clear();
+    store.anti_entropy().await.unwrap();
+    assert!(calls.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn anti_entropy_should_not_restore_deleted_key() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    let store = owner();
+    let mut clock = VectorClock::new();
+    clock.set(8, 2);
+    store.put_local(b"4".to_vec(), Versioned::tombstone(clock)).unwrap();
+
+    // Both replicas were down when the key was deleted
+    let first = Arc::new(KvStore::new(replicated_service(16, 3)));
+    first.put_local(b"4".to_vec(), written_by(b"value", 8)).unwrap();
+    let second = Arc::new(KvStore::new(replicated_service(21, 3)));
+    second.put_local(b"4".to_vec(), written_by(b"value", 8)).unwrap();
+
+    let calls: Calls = Arc::new(Mutex::new(Vec::new()));
+    let replicas = HashMap::from([(42016, first.clone()), (42021, second.clone())]);
+    ctx.expect().returning(move |addr, _| {
+        replica(addr.port(), replicas.get(&addr.port()).cloned(), calls.clone())
+    });
+
+    store.anti_entropy().await.unwrap();
+
+    for store in [&store, first.as_ref(), second.as_ref()] {
+        let versions = store.get_local(b"4").unwrap();
+        assert_eq!(versions.len(), 1);
+        assert!(versions[0].is_tombstone());
+    }
+}
+
+#[tokio::test]
+async fn anti_entropy_should_repair_remaining_replicas_when_one_fails() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    let store = owner();
+    store.put_local(b"4".to_vec(), written_by(b"value", 8)).unwrap();
+
+    let missed = Arc::new(KvStore::new(replicated_service(21, 3)));
+    let calls: Calls = Arc::new(Mutex::new(Vec::new()));
+    let replicas = HashMap::from([(42021, missed.clone())]);
+    ctx.expect().returning(move |addr, _| {
+        replica(addr.port(), replicas.get(&addr.port()).cloned(), calls.clone())
+    });
+
+    let result = store.anti_entropy().await;
+
+    assert!(matches!(result, Err(ServiceError::Unreachable(node)) if node == tests::node(16)));
+    assert_eq!(value(missed.get_local(b"4").unwrap()), Some(b"value".to_vec()));
+}
+
+#[tokio::test]
+async fn anti_entropy_should_wait_for_predecessor() {
+    let store: KvStore<MockClient> = KvStore::new(replicated_service(8, 3));
+    store.put_local(b"4".to_vec(), written_by(b"value", 8)).unwrap();
+
+    assert!(store.anti_entropy().await.is_ok());
+}
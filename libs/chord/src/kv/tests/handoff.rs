@@ -0,0 +1,146 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use crate::client::{ClientError, MockClient};
+use crate::{Config, Consistency, KvStore, NodeService, RingConfig, ServiceError};
+use crate::kv::tests::{replicated_service, NumericHasher};
+use crate::service::tests::{self, get_lock, MTX};
+
+/// Store of the node 8 whose successor 16 owns the key "12", the hints count as acknowledgements
+/// of the writes
+fn store(hint_capacity: usize, hint_ttl: Duration) -> KvStore<MockClient> {
+    let config = Config {
+        ring: RingConfig::with_hasher(64, NumericHasher),
+        hint_capacity,
+        hint_ttl,
+        sloppy_quorum: true,
+        ..Config::default()
+    };
+    let service = NodeService::with_id_and_config(8, SocketAddr::from(([127, 0, 0, 1], 42008)), config);
+    service.store().set_successor(tests::node(16));
+
+    KvStore::new(service)
+}
+
+/// Client of the node 16, which refuses the connections until it's up
+fn owner(up: Arc<AtomicBool>, written: Arc<Mutex<Vec<Vec<u8>>>>) -> MockClient {
+    let mut client = MockClient::new();
+    let reachable = up.clone();
+    client.expect_ping()
+        .returning(move || {
+            match reachable.load(Ordering::SeqCst) {
+                true => Ok(()),
+                false => Err(ClientError::ConnectionFailed(tests::node(16))),
+            }
+        });
+    client.expect_put()
+        .returning(move |key, _| {
+            if !up.load(Ordering::SeqCst) {
+                return Err(ClientError::ConnectionFailed(tests::node(16)));
+            }
+
+            written.lock().unwrap().push(key);
+            Ok(())
+        });
+    client
+}
+
+#[tokio::test]
+async fn write_to_unreachable_replica_should_be_replayed_once_it_is_back() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    let up = Arc::new(AtomicBool::new(false));
+    let written = Arc::new(Mutex::new(Vec::new()));
+    let (reachable, delivered) = (up.clone(), written.clone());
+    ctx.expect().returning(move |_, _| owner(reachable.clone(), delivered.clone()));
+
+    let store = store(16, Duration::from_secs(60));
+    store.put(b"12", b"value".to_vec()).await.unwrap();
+    store.put(b"14", b"value".to_vec()).await.unwrap();
+    assert_eq!(store.pending_hints(), 2);
+
+    // The replica is still down, the hints are kept
+    store.replay_hints().await.unwrap();
+    assert_eq!(store.pending_hints(), 2);
+
+    up.store(true, Ordering::SeqCst);
+    store.replay_hints().await.unwrap();
+
+    assert_eq!(store.pending_hints(), 0);
+    assert_eq!(*written.lock().unwrap(), vec![b"12".to_vec(), b"14".to_vec()]);
+}
+
+#[tokio::test]
+async fn hints_should_not_count_as_acknowledgements_without_sloppy_quorum() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    let up = Arc::new(AtomicBool::new(false));
+    let written = Arc::new(Mutex::new(Vec::new()));
+    let (reachable, delivered) = (up.clone(), written.clone());
+    ctx.expect().returning(move |_, _| {
+        let mut client = owner(reachable.clone(), delivered.clone());
+        client.expect_successor_list()
+            .returning(|| Ok(vec![tests::node(21), tests::node(32)]));
+        client
+    });
+
+    // All the replicas 16, 21 and 32 of the key "12" are unreachable
+    let service = replicated_service(8, 3);
+    service.store().set_successor(tests::node(16));
+    let store = KvStore::new(service);
+
+    let result = store.put_with(b"12", b"value".to_vec(), Consistency::All).await;
+    assert!(matches!(result, Err(ServiceError::QuorumNotReached(3, 0))));
+    let result = store.put_with(b"12", b"value".to_vec(), Consistency::One).await;
+    assert!(matches!(result, Err(ServiceError::QuorumNotReached(1, 0))));
+
+    // The hints are still delivered once the replicas are back
+    assert_eq!(store.pending_hints(), 6);
+    up.store(true, Ordering::SeqCst);
+    store.replay_hints().await.unwrap();
+    assert_eq!(store.pending_hints(), 0);
+    assert_eq!(written.lock().unwrap().len(), 6);
+}
+
+#[tokio::test]
+async fn write_should_fail_when_hint_queue_is_full() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    let written = Arc::new(Mutex::new(Vec::new()));
+    ctx.expect().returning(move |_, _| owner(Arc::new(AtomicBool::new(false)), written.clone()));
+
+    let store = store(1, Duration::from_secs(60));
+    store.put(b"12", b"value".to_vec()).await.unwrap();
+
+    let result = store.put(b"14", b"value".to_vec()).await;
+    assert!(matches!(result, Err(ServiceError::QuorumNotReached(1, 0))));
+    assert_eq!(store.pending_hints(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn expired_hints_should_be_dropped() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    let up = Arc::new(AtomicBool::new(false));
+    let written = Arc::new(Mutex::new(Vec::new()));
+    let (reachable, delivered) = (up.clone(), written.clone());
+    ctx.expect().returning(move |_, _| owner(reachable.clone(), delivered.clone()));
+
+    let store = store(16, Duration::from_secs(60));
+    store.put(b"12", b"value".to_vec()).await.unwrap();
+
+    tokio::time::sleep(Duration::from_secs(30)).await;
+    store.put(b"14", b"value".to_vec()).await.unwrap();
+
+    tokio::time::sleep(Duration::from_secs(45)).await;
+    assert_eq!(store.pending_hints(), 1);
+
+    up.store(true, Ordering::SeqCst);
+    store.replay_hints().await.unwrap();
+    assert_eq!(*written.lock().unwrap(), vec![b"14".to_vec()]);
+}
@@ -0,0 +1,197 @@
+use std::sync::Arc;
+use crate::client::{ClientError, MockClient};
+use crate::KvStore;
+use crate::kv::TRANSFER_BATCH_SIZE;
+use crate::kv::tests::{replicated_service, service, value, version, written_by};
+use crate::service::tests::{self, get_lock, MTX};
+
+fn successor_with_keys(keys: &[u64]) -> Arc<KvStore<MockClient>> {
+    let successor = KvStore::new(service(16));
+    for key in keys {
+        successor.put_local(key.to_string().into_bytes(), version(b"value")).unwrap();
+    }
+
+    Arc::new(successor)
+}
+
+#[tokio::test]
+async fn join_should_take_over_keys_from_successor() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    let mut keys: Vec<u64> = (1000..1300).collect();
+    keys.extend([4, 10, 12, 16]);
+    let successor = successor_with_keys(&keys);
+
+    let remote = successor.clone();
+    ctx.expect().returning(move |_, _| {
+        let mut client = MockClient::new();
+        client.expect_find_successor()
+            .returning(|_| {
+                Ok(tests::node(16))
+            });
+        client.expect_predecessor()
+            .returning(|| Ok(None));
+        let store = remote.clone();
+        client.expect_transfer_keys()
+            .returning(move |from, to, after| {
+                assert_eq!((from, to), (16, 8));
+                Ok(store.transfer_keys_local(from, to, after.as_deref()).unwrap())
+            });
+        let store = remote.clone();
+        client.expect_confirm_transfer()
+            .returning(move |entries| {
+                store.confirm_transfer_local(entries).unwrap();
+                Ok(())
+            });
+        client
+    });
+
+    let store: KvStore<MockClient> = KvStore::new(service(8));
+    store.join(tests::node(16)).await.unwrap();
+
+    assert_eq!(store.len(), 301);
+    assert_eq!(value(store.get_local(b"4").unwrap()), Some(b"value".to_vec()));
+    assert_eq!(value(store.get_local(b"1299").unwrap()), Some(b"value".to_vec()));
+    assert!(store.get_local(b"10").unwrap().is_empty());
+
+    assert_eq!(successor.len(), 3);
+    assert!(successor.get_local(b"4").unwrap().is_empty());
+    assert_eq!(value(successor.get_local(b"12").unwrap()), Some(b"value".to_vec()));
+}
+
+#[tokio::test]
+async fn join_should_only_take_over_keys_after_predecessor() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    let successor = successor_with_keys(&[1, 4, 10, 1000]);
+
+    let remote = successor.clone();
+    ctx.expect().returning(move |_, _| {
+        let mut client = MockClient::new();
+        client.expect_find_successor()
+            .returning(|_| {
+                Ok(tests::node(16))
+            });
+        client.expect_predecessor()
+            .returning(|| Ok(Some(tests::node(2))));
+        let store = remote.clone();
+        client.expect_transfer_keys()
+            .returning(move |from, to, after| {
+                assert_eq!((from, to), (2, 8));
+                Ok(store.transfer_keys_local(from, to, after.as_deref()).unwrap())
+            });
+        let store = remote.clone();
+        client.expect_confirm_transfer()
+            .returning(move |entries| {
+                store.confirm_transfer_local(entries).unwrap();
+                Ok(())
+            });
+        client
+    });
+
+    let store: KvStore<MockClient> = KvStore::new(service(8));
+    store.join(tests::node(16)).await.unwrap();
+
+    assert_eq!(store.len(), 1);
+    assert_eq!(value(store.get_local(b"4").unwrap()), Some(b"value".to_vec()));
+
+    assert_eq!(successor.len(), 3);
+    assert_eq!(value(successor.get_local(b"1").unwrap()), Some(b"value".to_vec()));
+    assert_eq!(value(successor.get_local(b"1000").unwrap()), Some(b"value".to_vec()));
+}
+
+#[test]
+fn confirm_transfer_should_keep_versions_written_during_transfer() {
+    let successor = successor_with_keys(&[2, 4]);
+    let transferred = successor.transfer_keys_local(16, 8, None).unwrap();
+    assert_eq!(transferred.len(), 2);
+
+    successor.put_local(b"4".to_vec(), written_by(b"new", 16)).unwrap();
+    successor.confirm_transfer_local(transferred).unwrap();
+
+    assert!(successor.get_local(b"2").unwrap().is_empty());
+    assert_eq!(value(successor.get_local(b"4").unwrap()), Some(b"new".to_vec()));
+}
+
+#[tokio::test]
+async fn failed_transfer_should_keep_keys_on_successor() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    let successor = successor_with_keys(&[4, 10]);
+
+    let remote = successor.clone();
+    ctx.expect().returning(move |_, _| {
+        let mut client = MockClient::new();
+        client.expect_find_successor()
+            .returning(|_| {
+                Ok(tests::node(16))
+            });
+        client.expect_predecessor()
+            .returning(|| Ok(None));
+        let store = remote.clone();
+        client.expect_transfer_keys()
+            .returning(move |from, to, after| {
+                match after {
+                    None => Ok(store.transfer_keys_local(from, to, None).unwrap()),
+                    Some(_) => Err(ClientError::ConnectionFailed(tests::node(16))),
+                }
+            });
+        client.expect_confirm_transfer()
+            .never();
+        client
+    });
+
+    let store: KvStore<MockClient> = KvStore::new(service(8));
+    assert!(store.join(tests::node(16)).await.is_err());
+
+    assert_eq!(successor.len(), 2);
+}
+
+#[tokio::test]
+async fn join_with_replication_should_keep_keys_on_successor() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    let successor = successor_with_keys(&[4, 10]);
+
+    let remote = successor.clone();
+    ctx.expect().returning(move |_, _| {
+        let mut client = MockClient::new();
+        client.expect_find_successor()
+            .returning(|_| {
+                Ok(tests::node(16))
+            });
+        client.expect_predecessor()
+            .returning(|| Ok(None));
+        let store = remote.clone();
+        client.expect_transfer_keys()
+            .returning(move |from, to, after| {
+                Ok(store.transfer_keys_local(from, to, after.as_deref()).unwrap())
+            });
+        client.expect_confirm_transfer()
+            .never();
+        client
+    });
+
+    let store: KvStore<MockClient> = KvStore::new(replicated_service(8, 2));
+    store.join(tests::node(16)).await.unwrap();
+
+    assert_eq!(value(store.get_local(b"4").unwrap()), Some(b"value".to_vec()));
+    assert_eq!(successor.len(), 2);
+}
+
+#[test]
+fn transfer_keys_should_be_sent_in_batches() {
+    let keys: Vec<u64> = (1000..1300).collect();
+    let successor = successor_with_keys(&keys);
+
+    let first = successor.transfer_keys_local(16, 8, None).unwrap();
+    assert_eq!(first.len(), TRANSFER_BATCH_SIZE);
+
+    let second = successor.transfer_keys_local(16, 8, first.last().map(|(key, _)| key.as_slice())).unwrap();
+    assert_eq!(second.len(), 300 - TRANSFER_BATCH_SIZE);
+    assert!(successor.transfer_keys_local(16, 8, second.last().map(|(key, _)| key.as_slice())).unwrap().is_empty());
+}
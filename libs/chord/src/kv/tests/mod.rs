@@ -0,0 +1,54 @@
+use std::net::SocketAddr;
+use crate::{Config, IdHasher, NodeService, RingConfig, VectorClock, Versioned, Versions};
+use crate::client::MockClient;
+
+mod store;
+mod migration;
+mod replication;
+mod quorum;
+mod versions;
+mod anti_entropy;
+mod handoff;
+mod scan;
+mod tombstone;
+
+/// Hasher reading the key as a decimal number, so the tests can choose the ids of the keys
+#[derive(Debug)]
+struct NumericHasher;
+
+impl IdHasher for NumericHasher {
+    fn hash(&self, bytes: &[u8]) -> u64 {
+        std::str::from_utf8(bytes).unwrap().parse().unwrap()
+    }
+}
+
+fn service(id: u64) -> NodeService<MockClient> {
+    replicated_service(id, 1)
+}
+
+fn replicated_service(id: u64, replication_factor: usize) -> NodeService<MockClient> {
+    let config = Config {
+        ring: RingConfig::with_hasher(64, NumericHasher),
+        replication_factor,
+        ..Config::default()
+    };
+
+    NodeService::with_id_and_config(id, SocketAddr::from(([127, 0, 0, 1], 42000 + id as u16)), config)
+}
+
+fn version(value: &[u8]) -> Versioned {
+    Versioned::new(value.to_vec(), VectorClock::new())
+}
+
+/// Version written through the given node, without reading the key first
+fn written_by(value: &[u8], node_id: u64) -> Versioned {
+    let mut clock = VectorClock::new();
+    clock.increment(node_id);
+
+    Versioned::new(value.to_vec(), clock)
+}
+
+/// Get the value of the key, if it has a single version
+fn value(versions: Vec<Versioned>) -> Option<Vec<u8>> {
+    Versions::new(versions).value().map(|value| value.to_vec())
+}
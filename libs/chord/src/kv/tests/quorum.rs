@@ -0,0 +1,117 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+use crate::client::{ClientError, MockClient};
+use crate::{Consistency, KvStore, ServiceError, VectorClock, Versioned};
+use crate::kv::tests::replicated_service;
+use crate::service::tests::{self, get_lock, MTX};
+
+fn version(value: &[u8], counters: &[(u64, u64)]) -> Versioned {
+    let mut clock = VectorClock::new();
+    for (node_id, counter) in counters {
+        for _ in 0..*counter {
+            clock.increment(*node_id);
+        }
+    }
+
+    Versioned::new(value.to_vec(), clock)
+}
+
+// Replicas of the key "12" are the nodes 16, 21 and 32. The node 32 doesn't answer.
+fn replica(addr: SocketAddr) -> MockClient {
+    let mut client = MockClient::new();
+    client.expect_successor_list()
+        .returning(|| {
+            Ok(vec![tests::node(21), tests::node(32)])
+        });
+    client.expect_put()
+        .returning(move |_, _| {
+            match addr.port() {
+                42032 => Err(ClientError::Timeout(tests::node(32))),
+                _ => Ok(()),
+            }
+        });
+    client.expect_get()
+        .returning(move |_| {
+            match addr.port() {
+                42016 => Ok(vec![version(b"new", &[(16, 2)])]),
+                42021 => Ok(vec![version(b"old", &[(16, 1)]), version(b"other", &[(21, 1)])]),
+                _ => Err(ClientError::Timeout(tests::node(32))),
+            }
+        });
+    client
+}
+
+fn store() -> KvStore<MockClient> {
+    let service = replicated_service(8, 3);
+    service.store().set_successor(tests::node(16));
+    KvStore::new(service)
+}
+
+#[tokio::test]
+async fn write_should_complete_once_enough_replicas_acknowledge() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+    ctx.expect().returning(|addr, _| replica(addr));
+
+    let store = store();
+
+    assert!(store.put_with(b"12", b"value".to_vec(), Consistency::One).await.is_ok());
+    assert!(store.put_with(b"12", b"value".to_vec(), Consistency::Quorum).await.is_ok());
+
+    let result = store.put_with(b"12", b"value".to_vec(), Consistency::All).await;
+    assert!(matches!(result, Err(ServiceError::QuorumNotReached(3, 2))));
+}
+
+#[tokio::test]
+async fn read_should_merge_versions_of_replicas() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+    ctx.expect().returning(|addr, _| replica(addr));
+
+    let store = store();
+
+    let versions = store.get_with(b"12", Consistency::Quorum).await.unwrap();
+    let mut values = versions.values();
+    values.sort();
+    assert_eq!(values, vec![b"new".as_slice(), b"other".as_slice()]);
+    assert!(versions.is_conflict());
+
+    let result = store.get_with(b"12", Consistency::All).await;
+    assert!(matches!(result, Err(ServiceError::QuorumNotReached(3, 2))));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn slow_replicas_should_not_delay_write_with_consistency_one() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(move |addr, _| {
+        let mut client = MockClient::new();
+        client.expect_successor_list()
+            .returning(|| {
+                Ok(vec![tests::node(21), tests::node(32)])
+            });
+        client.expect_put()
+            .returning(move |_, _| {
+                if addr.port() != 42016 {
+                    std::thread::sleep(Duration::from_millis(500));
+                }
+                Ok(())
+            });
+        client
+    });
+
+    let store = store();
+    let result = tokio::time::timeout(Duration::from_millis(250), store.put_with(b"12", b"value".to_vec(), Consistency::One)).await;
+
+    assert!(matches!(result, Ok(Ok(()))));
+}
+
+#[test]
+fn consistency_should_give_number_of_required_replicas() {
+    assert_eq!(Consistency::One.required(1), 1);
+    assert_eq!(Consistency::Quorum.required(1), 1);
+    assert_eq!(Consistency::Quorum.required(2), 2);
+    assert_eq!(Consistency::Quorum.required(5), 3);
+    assert_eq!(Consistency::All.required(5), 5);
+}
@@ -0,0 +1,186 @@
+use std::sync::{Arc, Mutex};
+use mockall::predicate;
+use crate::client::{ClientError, MockClient};
+use crate::{KvStore, Node, Versioned};
+use crate::kv::tests::{replicated_service, value, version};
+use crate::service::tests::{self, get_lock, MTX};
+
+#[tokio::test]
+async fn replicas_should_be_on_distinct_hosts() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|_, _| {
+        let mut client = MockClient::new();
+        client.expect_successor_list()
+            .returning(|| {
+                let same_host = Node::with_id(18, tests::node(16).addr());
+                Ok(vec![same_host, tests::node(21), tests::node(32), tests::node(40)])
+            });
+        client
+    });
+
+    let service = replicated_service(8, 3);
+    service.store().set_successor(tests::node(16));
+    let store: KvStore<MockClient> = KvStore::new(service);
+
+    let ids: Vec<u64> = store.replicas(b"12").await.unwrap().iter().map(|n| n.id()).collect();
+    assert_eq!(ids, vec![16, 21, 32]);
+}
+
+#[tokio::test]
+async fn put_should_write_to_all_replicas() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    let written = Arc::new(Mutex::new(Vec::new()));
+    let replicas = written.clone();
+    ctx.expect().returning(move |addr, _| {
+        let mut client = MockClient::new();
+        client.expect_successor_list()
+            .returning(|| {
+                Ok(vec![tests::node(21), tests::node(32)])
+            });
+        let replicas = replicas.clone();
+        client.expect_put()
+            .with(predicate::eq(b"12".to_vec()), predicate::function(|v: &Versioned| v.value.as_deref() == Some(b"value".as_slice())))
+            .returning(move |_, _| {
+                replicas.lock().unwrap().push(addr.port());
+                Ok(())
+            });
+        client
+    });
+
+    let service = replicated_service(8, 2);
+    service.store().set_successor(tests::node(16));
+    let store: KvStore<MockClient> = KvStore::new(service);
+
+    store.put(b"12", b"value".to_vec()).await.unwrap();
+
+    let mut written = written.lock().unwrap().clone();
+    written.sort();
+    assert_eq!(written, vec![42016, 42021]);
+}
+
+#[tokio::test]
+async fn local_owner_should_replicate_to_its_successors() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr, _| {
+        let mut client = MockClient::new();
+        client.expect_find_successor()
+            .returning(|_| {
+                Ok(tests::node(8))
+            });
+        if addr.port() == 42016 {
+            client.expect_put()
+                .with(predicate::eq(b"4".to_vec()), predicate::function(|v: &Versioned| v.value.as_deref() == Some(b"value".as_slice())))
+                .returning(|_, _| {
+                    Ok(())
+                });
+        }
+        client
+    });
+
+    let service = replicated_service(8, 2);
+    service.store().set_successor(tests::node(16));
+    service.store().update_successor_list(vec![tests::node(21)]);
+    let store: KvStore<MockClient> = KvStore::new(service);
+
+    let ids: Vec<u64> = store.replicas(b"4").await.unwrap().iter().map(|n| n.id()).collect();
+    assert_eq!(ids, vec![8, 16]);
+
+    store.put(b"4", b"value".to_vec()).await.unwrap();
+    assert_eq!(value(store.get_local(b"4").unwrap()), Some(b"value".to_vec()));
+}
+
+#[tokio::test]
+async fn get_should_fall_back_to_next_replica() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr, _| {
+        let mut client = MockClient::new();
+        client.expect_successor_list()
+            .returning(|| {
+                Ok(vec![tests::node(21), tests::node(32)])
+            });
+        client.expect_get()
+            .returning(move |_| {
+                if addr.port() == 42016 {
+                    Err(ClientError::ConnectionFailed(tests::node(16)))
+                } else {
+                    Ok(vec![version(b"value")])
+                }
+            });
+        client
+    });
+
+    let service = replicated_service(8, 3);
+    service.store().set_successor(tests::node(16));
+    let store: KvStore<MockClient> = KvStore::new(service);
+
+    assert_eq!(store.get(b"12").await.unwrap().value(), Some(b"value".as_slice()));
+}
+
+#[tokio::test]
+async fn sync_replicas_should_copy_owned_keys_when_replicas_change() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    let replicated = Arc::new(Mutex::new(Vec::new()));
+    let calls = replicated.clone();
+    ctx.expect().returning(move |addr, _| {
+        let mut client = MockClient::new();
+        let calls = calls.clone();
+        client.expect_replicate()
+            .returning(move |entries| {
+                let keys: Vec<Vec<u8>> = entries.into_iter().map(|(key, _)| key).collect();
+                calls.lock().unwrap().push((addr.port(), keys));
+                Ok(())
+            });
+        client
+    });
+
+    let service = replicated_service(8, 3);
+    service.store().set_successor(tests::node(16));
+    service.store().update_successor_list(vec![tests::node(21), tests::node(32)]);
+    service.store().set_predecessor(tests::node(2));
+    let store: KvStore<MockClient> = KvStore::new(service);
+    store.put_local(b"1".to_vec(), version(b"value")).unwrap();
+    store.put_local(b"4".to_vec(), version(b"value")).unwrap();
+    store.put_local(b"8".to_vec(), version(b"value")).unwrap();
+
+    store.sync_replicas().await.unwrap();
+    let owned = vec![b"4".to_vec(), b"8".to_vec()];
+    assert_eq!(*replicated.lock().unwrap(), vec![(42016, owned.clone()), (42021, owned.clone())]);
+
+    // Nothing changed, nothing to replicate
+    store.sync_replicas().await.unwrap();
+    assert_eq!(replicated.lock().unwrap().len(), 2);
+
+    // The predecessor died and the node took over its range
+    store.node().store().set_predecessor(tests::node(0));
+    store.sync_replicas().await.unwrap();
+    let owned = vec![b"1".to_vec(), b"4".to_vec(), b"8".to_vec()];
+    assert_eq!(replicated.lock().unwrap()[2..], [(42016, owned.clone()), (42021, owned)]);
+}
+
+#[tokio::test]
+async fn sync_replicas_should_wait_for_predecessor() {
+    let store: KvStore<MockClient> = KvStore::new(replicated_service(8, 3));
+    store.put_local(b"4".to_vec(), version(b"value")).unwrap();
+
+    assert!(store.sync_replicas().await.is_ok());
+}
+
+#[test]
+fn replicate_should_store_entries_locally() {
+    let store: KvStore<MockClient> = KvStore::new(replicated_service(8, 3));
+
+    store.replicate_local(vec![(b"4".to_vec(), vec![version(b"value")]), (b"20".to_vec(), vec![version(b"other")])]).unwrap();
+
+    assert_eq!(value(store.get_local(b"20").unwrap()), Some(b"other".to_vec()));
+    assert_eq!(store.len(), 2);
+}
@@ -0,0 +1,150 @@
+use std::net::SocketAddr;
+use crate::client::{ClientError, MockClient};
+use crate::{Entry, KvStore, Node, ScanToken, ServiceError};
+use crate::kv::tests::{service, version};
+use crate::service::tests::{self, get_lock, MTX};
+
+/// The nodes of the ring, the store under test runs on node 8
+const NODES: [u64; 3] = [8, 16, 40];
+
+fn owner(id: u64) -> Node {
+    let owner = NODES.iter().find(|node| id <= **node).unwrap_or(&NODES[0]);
+    tests::node(*owner)
+}
+
+/// Get the page of the keys of a remote node, the same way a node would scan its storage
+fn remote_scan(node_id: u64, from: u64, to: u64, after: Option<Vec<u8>>, limit: usize) -> Vec<Entry> {
+    let keys: &[u64] = match node_id {
+        16 => &[12, 14, 16],
+        40 => &[20, 33, 40],
+        _ => &[],
+    };
+    let mut ids: Vec<u64> = keys.iter().copied().filter(|id| Node::is_between_on_ring(*id, from, to)).collect();
+    ids.sort_by_key(|id| id.wrapping_sub(from));
+    let after = after.map(|key| String::from_utf8(key).unwrap().parse::<u64>().unwrap());
+
+    ids.into_iter()
+        .skip_while(|id| after.is_some_and(|after| id.wrapping_sub(from) <= after.wrapping_sub(from)))
+        .take(limit)
+        .map(|id| (id.to_string().into_bytes(), vec![version(b"value")]))
+        .collect()
+}
+
+fn keys(entries: &[Entry]) -> Vec<u64> {
+    entries.iter().map(|(key, _)| std::str::from_utf8(key).unwrap().parse().unwrap()).collect()
+}
+
+fn store() -> KvStore<MockClient> {
+    let service = service(8);
+    service.store().set_successor(tests::node(16));
+    service.store().set_predecessor(tests::node(40));
+
+    let store = KvStore::new(service);
+    for key in ["2", "5", "8", "45", "60"] {
+        store.put_local(key.as_bytes().to_vec(), version(b"value")).unwrap();
+    }
+
+    store
+}
+
+fn ring_context() -> impl Drop {
+    let ctx = MockClient::init_context();
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let node_id = addr.port() as u64 - 42000;
+        let mut client = MockClient::new();
+        client.expect_find_successor()
+            .returning(|id| Ok(owner(id)));
+        client.expect_scan()
+            .returning(move |from, to, after, limit| Ok(remote_scan(node_id, from, to, after, limit)));
+        client
+    });
+
+    ctx
+}
+
+#[tokio::test]
+async fn single_node_should_scan_range_in_ring_order() {
+    let store: KvStore<MockClient> = KvStore::new(service(8));
+    for key in ["4", "10", "20", "30", "50"] {
+        store.put_local(key.as_bytes().to_vec(), version(b"value")).unwrap();
+    }
+
+    let page = store.scan(5, 30, 10, None).await.unwrap();
+    assert_eq!(keys(&page.entries), vec![10, 20, 30]);
+    assert_eq!(page.next, None);
+
+    let page = store.scan(25, 5, 10, None).await.unwrap();
+    assert_eq!(keys(&page.entries), vec![30, 50, 4]);
+
+    let page = store.scan(20, 20, 3, None).await.unwrap();
+    assert_eq!(keys(&page.entries), vec![30, 50, 4]);
+    assert_eq!(page.next, Some(ScanToken::new(b"4".to_vec())));
+
+    let page = store.scan(20, 20, 3, page.next.as_ref()).await.unwrap();
+    assert_eq!(keys(&page.entries), vec![10, 20]);
+    assert_eq!(page.next, None);
+}
+
+#[tokio::test]
+async fn scan_should_walk_successors_page_by_page() {
+    let _m = get_lock(&MTX).await;
+    let _ctx = ring_context();
+    let store = store();
+
+    let mut scanned = Vec::new();
+    let mut token = None;
+    loop {
+        let page = store.scan(30, 30, 3, token.as_ref()).await.unwrap();
+        assert!(page.entries.len() <= 3);
+        scanned.extend(keys(&page.entries));
+        token = page.next;
+        if token.is_none() {
+            break;
+        }
+    }
+
+    assert_eq!(scanned, vec![33, 40, 45, 60, 2, 5, 8, 12, 14, 16, 20]);
+}
+
+#[tokio::test]
+async fn scan_should_stop_at_end_of_range() {
+    let _m = get_lock(&MTX).await;
+    let _ctx = ring_context();
+    let store = store();
+
+    let page = store.scan(10, 35, 100, None).await.unwrap();
+    assert_eq!(keys(&page.entries), vec![12, 14, 16, 20, 33]);
+    assert_eq!(page.next, None);
+
+    let page = store.scan(40, 8, 100, None).await.unwrap();
+    assert_eq!(keys(&page.entries), vec![45, 60, 2, 5, 8]);
+    assert_eq!(page.next, None);
+}
+
+#[tokio::test]
+async fn scan_should_fail_when_owner_fails() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|_, _| {
+        let mut client = MockClient::new();
+        client.expect_find_successor()
+            .returning(|id| Ok(owner(id)));
+        client.expect_scan()
+            .returning(|_, _, _, _| Err(ClientError::Timeout(tests::node(16))));
+        client
+    });
+    let store = store();
+
+    let result = store.scan(4, 20, 10, None).await;
+
+    assert!(matches!(result, Err(ServiceError::Timeout(_))));
+}
+
+#[tokio::test]
+async fn scan_should_reject_zero_limit() {
+    let store = store();
+
+    let result = store.scan(4, 20, 0, None).await;
+    assert!(matches!(result, Err(ServiceError::InvalidArgument(_))));
+}
@@ -0,0 +1,149 @@
+use mockall::predicate;
+use crate::client::{ClientError, MockClient};
+use crate::{KvStore, ServiceError, Versioned, WalBackend};
+use crate::kv::tests::{service, value, version};
+use crate::service::tests::{self, get_lock, MTX};
+
+#[tokio::test]
+async fn single_node_should_serve_keys_locally() {
+    let store: KvStore<MockClient> = KvStore::new(service(8));
+
+    assert!(store.get(b"40").await.unwrap().is_empty());
+
+    store.put(b"40", b"value".to_vec()).await.unwrap();
+    assert_eq!(store.get(b"40").await.unwrap().value(), Some(b"value".as_slice()));
+    assert_eq!(store.len(), 1);
+
+    store.delete(b"40").await.unwrap();
+    assert!(store.get(b"40").await.unwrap().is_empty());
+    // The tombstone is kept until it expires
+    assert_eq!(store.len(), 1);
+}
+
+#[tokio::test]
+async fn keys_should_be_stored_on_their_successor() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|_, _| {
+        let mut client = MockClient::new();
+        client.expect_put()
+            .with(predicate::eq(b"12".to_vec()), predicate::function(|v: &Versioned| v.value.as_deref() == Some(b"value".as_slice())))
+            .returning(|_, _| {
+                Ok(())
+            });
+        client.expect_get()
+            .with(predicate::eq(b"12".to_vec()))
+            .returning(|_| {
+                Ok(vec![version(b"value")])
+            });
+        client.expect_put()
+            .with(predicate::eq(b"12".to_vec()), predicate::function(Versioned::is_tombstone))
+            .returning(|_, _| {
+                Ok(())
+            });
+        client
+    });
+
+    let service = service(8);
+    service.store().set_successor(tests::node(16));
+    let store: KvStore<MockClient> = KvStore::new(service);
+
+    store.put(b"12", b"value".to_vec()).await.unwrap();
+    assert_eq!(store.get(b"12").await.unwrap().value(), Some(b"value".as_slice()));
+    store.delete(b"12").await.unwrap();
+
+    assert!(store.is_empty());
+}
+
+#[tokio::test]
+async fn keys_owned_by_current_node_should_be_stored_locally() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|_, _| {
+        let mut client = MockClient::new();
+        client.expect_find_successor()
+            .returning(|_| {
+                Ok(tests::node(8))
+            });
+        client
+    });
+
+    let service = service(8);
+    service.store().set_successor(tests::node(16));
+    let store: KvStore<MockClient> = KvStore::new(service);
+
+    store.put(b"4", b"value".to_vec()).await.unwrap();
+
+    assert_eq!(value(store.get_local(b"4").unwrap()), Some(b"value".to_vec()));
+}
+
+#[tokio::test]
+async fn put_should_fail_when_successor_times_out() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|_, _| {
+        let mut client = MockClient::new();
+        client.expect_put()
+            .returning(|_, _| {
+                Err(ClientError::Timeout(tests::node(16)))
+            });
+        client
+    });
+
+    let service = service(8);
+    service.store().set_successor(tests::node(16));
+    let store: KvStore<MockClient> = KvStore::new(service);
+
+    let result = store.put(b"12", b"value".to_vec()).await;
+    assert!(matches!(result, Err(ServiceError::QuorumNotReached(1, 0))));
+    assert_eq!(store.pending_hints(), 0);
+}
+
+#[tokio::test]
+async fn keys_should_survive_restart_with_wal_backend() {
+    let path = std::env::temp_dir().join(format!("chord-store-{}.wal", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let store: KvStore<MockClient> = KvStore::with_backend(service(8), WalBackend::open(&path).unwrap());
+    store.put(b"40", b"value".to_vec()).await.unwrap();
+    store.put(b"50", b"other".to_vec()).await.unwrap();
+    store.delete(b"50").await.unwrap();
+    drop(store);
+
+    let store: KvStore<MockClient> = KvStore::with_backend(service(8), WalBackend::open(&path).unwrap());
+    assert_eq!(store.get(b"40").await.unwrap().value(), Some(b"value".as_slice()));
+    assert!(store.get(b"50").await.unwrap().is_empty());
+    assert!(store.get_local(b"50").unwrap()[0].is_tombstone());
+    assert_eq!(store.len(), 2);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn write_after_restart_with_wal_backend_should_replace_previous_value() {
+    let path = std::env::temp_dir().join(format!("chord-store-clock-{}.wal", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let store: KvStore<MockClient> = KvStore::with_backend(service(8), WalBackend::open(&path).unwrap());
+    store.put(b"40", b"first".to_vec()).await.unwrap();
+    store.put(b"40", b"second".to_vec()).await.unwrap();
+    drop(store);
+
+    // The clock of the new write descends from the clocks issued before the restart
+    let store: KvStore<MockClient> = KvStore::with_backend(service(8), WalBackend::open(&path).unwrap());
+    store.put(b"40", b"third".to_vec()).await.unwrap();
+    assert_eq!(store.get_local(b"40").unwrap().len(), 1);
+    assert_eq!(store.get(b"40").await.unwrap().value(), Some(b"third".as_slice()));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn key_id_should_use_hasher_of_ring() {
+    let store: KvStore<MockClient> = KvStore::new(service(8));
+
+    assert_eq!(store.key_id(b"12"), 12);
+}
@@ -0,0 +1,45 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+use crate::{Config, KvStore, NodeService, RingConfig};
+use crate::client::MockClient;
+use crate::kv::tests::{value, NumericHasher};
+
+fn store(tombstone_ttl: Duration) -> KvStore<MockClient> {
+    let config = Config { ring: RingConfig::with_hasher(64, NumericHasher), tombstone_ttl, ..Config::default() };
+    KvStore::new(NodeService::with_id_and_config(8, SocketAddr::from(([127, 0, 0, 1], 42008)), config))
+}
+
+#[tokio::test(start_paused = true)]
+async fn collect_tombstones_should_drop_expired_deleted_keys() {
+    let store = store(Duration::from_secs(60));
+    store.put(b"40", b"value".to_vec()).await.unwrap();
+    store.put(b"50", b"value".to_vec()).await.unwrap();
+    store.delete(b"40").await.unwrap();
+
+    store.collect_tombstones().unwrap();
+    assert_eq!(store.len(), 2);
+
+    tokio::time::advance(Duration::from_secs(30)).await;
+    store.collect_tombstones().unwrap();
+    assert_eq!(store.len(), 2);
+
+    tokio::time::advance(Duration::from_secs(30)).await;
+    store.collect_tombstones().unwrap();
+    assert_eq!(store.len(), 1);
+    assert!(store.get_local(b"40").unwrap().is_empty());
+    assert_eq!(value(store.get_local(b"50").unwrap()), Some(b"value".to_vec()));
+}
+
+#[tokio::test(start_paused = true)]
+async fn collect_tombstones_should_keep_key_written_after_delete() {
+    let store = store(Duration::from_secs(60));
+    store.put(b"40", b"old".to_vec()).await.unwrap();
+    store.delete(b"40").await.unwrap();
+    store.collect_tombstones().unwrap();
+
+    store.put(b"40", b"new".to_vec()).await.unwrap();
+    tokio::time::advance(Duration::from_secs(60)).await;
+    store.collect_tombstones().unwrap();
+
+    assert_eq!(store.get(b"40").await.unwrap().value(), Some(b"new".as_slice()));
+}
@@ -0,0 +1,53 @@
+use crate::client::MockClient;
+use crate::{Consistency, KvStore};
+use crate::kv::tests::{service, written_by};
+
+#[tokio::test]
+async fn writes_through_the_same_node_should_replace_each_other() {
+    let store: KvStore<MockClient> = KvStore::new(service(8));
+
+    store.put(b"4", b"first".to_vec()).await.unwrap();
+    store.put(b"4", b"second".to_vec()).await.unwrap();
+
+    let versions = store.get(b"4").await.unwrap();
+    assert_eq!(versions.value(), Some(b"second".as_slice()));
+    assert_eq!(versions.context().get(8), 2);
+}
+
+#[tokio::test]
+async fn concurrent_writes_should_be_kept_as_siblings() {
+    let store: KvStore<MockClient> = KvStore::new(service(8));
+
+    store.put(b"4", b"local".to_vec()).await.unwrap();
+    // Written through another node, without reading the key first
+    store.put_local(b"4".to_vec(), written_by(b"remote", 16)).unwrap();
+
+    let versions = store.get(b"4").await.unwrap();
+    assert!(versions.is_conflict());
+    assert_eq!(versions.value(), None);
+
+    let mut values = versions.values();
+    values.sort();
+    assert_eq!(values, vec![b"local".as_slice(), b"remote".as_slice()]);
+}
+
+#[tokio::test]
+async fn write_with_context_should_resolve_siblings() {
+    let store: KvStore<MockClient> = KvStore::new(service(8));
+    store.put_local(b"4".to_vec(), written_by(b"a", 16)).unwrap();
+    store.put_local(b"4".to_vec(), written_by(b"b", 21)).unwrap();
+
+    let versions = store.get(b"4").await.unwrap();
+    assert!(versions.is_conflict());
+
+    store.put_with_context(b"4", b"ab".to_vec(), &versions.context(), Consistency::All).await.unwrap();
+
+    let versions = store.get(b"4").await.unwrap();
+    assert_eq!(versions.value(), Some(b"ab".as_slice()));
+    assert_eq!(versions.context().get(16), 1);
+    assert_eq!(versions.context().get(21), 1);
+
+    // An outdated version replicated late doesn't bring the conflict back
+    store.replicate_local(vec![(b"4".to_vec(), vec![written_by(b"a", 16)])]).unwrap();
+    assert_eq!(store.get(b"4").await.unwrap().value(), Some(b"ab".as_slice()));
+}
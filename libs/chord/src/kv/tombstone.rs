@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tokio::time::Instant;
+use crate::{Client, KvStore};
+use crate::kv::{StorageError, Versioned};
+
+/// The deleted keys stored on the node, with the time they were first found deleted
+pub(crate) struct Tombstones {
+    found: Mutex<HashMap<Vec<u8>, Instant>>,
+    ttl: Duration,
+}
+
+impl Tombstones {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self { found: Mutex::new(HashMap::new()), ttl }
+    }
+
+    // A key missing from the map is only found deleted again, so a panic while holding the lock
+    // can only make the tombstones be kept longer.
+    fn found(&self) -> MutexGuard<'_, HashMap<Vec<u8>, Instant>> {
+        self.found.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<C: Client> KvStore<C> {
+    /// Drop the keys stored on the current node which were deleted longer than
+    /// [`tombstone_ttl`](crate::Config::tombstone_ttl) ago.
+    ///
+    /// A key is deleted once all its versions are tombstones. The time a key was found deleted is
+    /// only kept in memory, so after a restart the tombstones are kept for another full
+    /// `tombstone_ttl`. A key written again in the meantime is kept with all its versions.
+    ///
+    /// > **Note**
+    /// >
+    /// > This method should be called periodically, see [`Maintenance`](crate::Maintenance).
+    pub fn collect_tombstones(&self) -> Result<(), StorageError> {
+        let now = Instant::now();
+        let entries = self.entries_in_range(0, 0)?;
+
+        let mut found = self.tombstones.found();
+        let mut deleted = HashMap::new();
+        let mut expired = Vec::new();
+        for (key, versions) in entries {
+            if !versions.iter().all(Versioned::is_tombstone) {
+                continue;
+            }
+
+            let since = found.remove(&key).unwrap_or(now);
+            if now.duration_since(since) >= self.tombstones.ttl {
+                expired.push(key);
+            } else {
+                deleted.insert(key, since);
+            }
+        }
+        *found = deleted;
+        drop(found);
+
+        let mut data = self.write_data();
+        let mut collected = 0;
+        for key in expired {
+            let id = self.key_id(&key);
+            // The key may have been written since it was read
+            if data.get(id, &key)?.is_some_and(|versions| versions.iter().all(Versioned::is_tombstone)) {
+                data.delete(id, &key)?;
+                collected += 1;
+            }
+        }
+
+        if collected > 0 {
+            log::debug!("Node {} dropped {} deleted keys", self.node.node().id(), collected);
+        }
+        Ok(())
+    }
+}
@@ -1,12 +1,24 @@
 mod client;
+mod config;
+mod hasher;
+mod host;
+mod kv;
+mod maintenance;
 mod service;
 mod node;
 
 use std::net::SocketAddr;
-use seahash::hash;
 
-pub use client::Client;
-pub use service::NodeService;
+pub use client::{Client, ClientError};
+pub use config::{Config, ConfigError, FixFingersMode, RingConfig};
+pub use hasher::{IdHasher, SeaHasher, Sha1Hasher};
+pub use host::VirtualHost;
+pub use kv::{Causality, Consistency, Entry, KvStore, MemoryBackend, MerkleTree, ScanPage, ScanToken, StorageBackend,
+             StorageError, VectorClock, Versioned, Versions, WalBackend};
+pub use maintenance::{Maintenance, MaintenanceConfig, Routine};
+pub use node::RoutingSnapshot;
+pub use service::error::ServiceError;
+pub use service::{Hop, LookupMode, LookupTrace, NodeHandle, NodeService};
 
 /// A reference to a node in the chord ring
 #[derive(Clone, PartialEq, Debug)]
@@ -17,11 +29,46 @@ pub struct Node {
 
 impl Node {
     pub fn new(addr: SocketAddr) -> Self {
-        Self { id: hash(&addr.to_string().as_bytes()), addr }
+        Self::with_ring(addr, &RingConfig::default())
+    }
+
+    /// Create a node with an id derived from the address on the given ring
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address of the node
+    /// * `ring` - The configuration of the ring
+    pub fn with_ring(addr: SocketAddr, ring: &RingConfig) -> Self {
+        Self { id: ring.id(addr.to_string().as_bytes()), addr }
+    }
+
+    /// Create a virtual node of the host with the given address
+    ///
+    /// The id is derived from the address together with the index of the virtual node, so every
+    /// virtual node of the host gets a different position on the ring.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address of the host
+    /// * `index` - The index of the virtual node on the host
+    /// * `ring` - The configuration of the ring
+    pub fn virtual_node(addr: SocketAddr, index: usize, ring: &RingConfig) -> Self {
+        Self { id: ring.id(format!("{}#{}", addr, index).as_bytes()), addr }
+    }
+
+    /// Returns true if both nodes run on the same physical host
+    ///
+    /// Virtual nodes of a host share the address of the host.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The node to compare with
+    pub fn is_same_host(&self, other: &Node) -> bool {
+        self.addr == other.addr
     }
 
     pub fn client<C: Client>(&self) -> C {
-        C::init(self.addr)
+        C::init(self.addr, self.id)
     }
 
     pub fn addr(&self) -> SocketAddr {
@@ -0,0 +1,316 @@
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use crate::{Client, KvStore, NodeHandle, RoutingSnapshot};
+use crate::service::error::ServiceError;
+
+/// Default interval between two stabilization rounds
+pub const DEFAULT_STABILIZE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default interval between two finger table refreshes
+pub const DEFAULT_FIX_FINGERS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Default interval between two predecessor checks
+pub const DEFAULT_CHECK_PREDECESSOR_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Default interval between two synchronizations of the replicas
+pub const DEFAULT_SYNC_REPLICAS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Default interval between two anti-entropy rounds
+pub const DEFAULT_ANTI_ENTROPY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Default interval between two deliveries of the hints of unreachable replicas
+pub const DEFAULT_REPLAY_HINTS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Default interval between two collections of the tombstones of deleted keys
+pub const DEFAULT_COLLECT_TOMBSTONES_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Default interval between two snapshots of the routing state
+pub const DEFAULT_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Default maximum random delay added to every interval
+pub const DEFAULT_JITTER: Duration = Duration::from_millis(250);
+
+/// The periodic routines of a node
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Routine {
+    Stabilize,
+    FixFingers,
+    CheckPredecessor,
+    SyncReplicas,
+    AntiEntropy,
+    ReplayHints,
+    CollectTombstones,
+    SaveRouting,
+}
+
+impl Display for Routine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Routine::Stabilize => write!(f, "stabilize"),
+            Routine::FixFingers => write!(f, "fix_fingers"),
+            Routine::CheckPredecessor => write!(f, "check_predecessor"),
+            Routine::SyncReplicas => write!(f, "sync_replicas"),
+            Routine::AntiEntropy => write!(f, "anti_entropy"),
+            Routine::ReplayHints => write!(f, "replay_hints"),
+            Routine::CollectTombstones => write!(f, "collect_tombstones"),
+            Routine::SaveRouting => write!(f, "save_routing"),
+        }
+    }
+}
+
+/// Configuration of the maintenance runner
+#[derive(Clone, Debug)]
+pub struct MaintenanceConfig {
+    /// Interval between two stabilization rounds
+    pub stabilize_interval: Duration,
+    /// Interval between two finger table refreshes
+    pub fix_fingers_interval: Duration,
+    /// Interval between two predecessor checks
+    pub check_predecessor_interval: Duration,
+    /// Interval between two synchronizations of the replicas of the key-value stores
+    pub sync_replicas_interval: Duration,
+    /// Interval between two anti-entropy rounds of the key-value stores
+    pub anti_entropy_interval: Duration,
+    /// Interval between two deliveries of the hints kept by the key-value stores
+    pub replay_hints_interval: Duration,
+    /// Interval between two collections of the tombstones kept by the key-value stores
+    pub collect_tombstones_interval: Duration,
+    /// Interval between two snapshots of the routing state of the nodes
+    pub snapshot_interval: Duration,
+    /// Maximum random delay added to every interval.
+    ///
+    /// It keeps the nodes of the ring from running their routines in lockstep.
+    pub jitter: Duration,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            stabilize_interval: DEFAULT_STABILIZE_INTERVAL,
+            fix_fingers_interval: DEFAULT_FIX_FINGERS_INTERVAL,
+            check_predecessor_interval: DEFAULT_CHECK_PREDECESSOR_INTERVAL,
+            sync_replicas_interval: DEFAULT_SYNC_REPLICAS_INTERVAL,
+            anti_entropy_interval: DEFAULT_ANTI_ENTROPY_INTERVAL,
+            replay_hints_interval: DEFAULT_REPLAY_HINTS_INTERVAL,
+            collect_tombstones_interval: DEFAULT_COLLECT_TOMBSTONES_INTERVAL,
+            snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+            jitter: DEFAULT_JITTER,
+        }
+    }
+}
+
+type ErrorCallback = Arc<dyn Fn(Routine, &ServiceError) + Send + Sync>;
+
+/// Maintenance runner of a node
+///
+/// The runner calls `stabilize`, `fix_fingers` and `check_predecessor` of the node, each on its
+/// own interval. Every routine runs in a separate tokio task, so a slow routine doesn't delay the
+/// others. Errors are logged and passed to the error callback, if one is set.
+///
+/// A single runner can maintain several nodes, e.g. all the virtual nodes of a host. On every tick
+/// the routine runs for each of the nodes in turn.
+///
+/// The key-value stores added with [`Maintenance::with_store`] get their replicas synchronized
+/// and repaired by the anti-entropy routine as well, their hints are delivered to the replicas
+/// which are back and the tombstones of their deleted keys are dropped once they expired.
+///
+/// The routing state of the nodes is saved periodically once a directory is set with
+/// [`Maintenance::with_snapshot_dir`].
+///
+/// The tasks are stopped when the runner is dropped.
+pub struct Maintenance<C: Client + 'static> {
+    nodes: Vec<NodeHandle<C>>,
+    stores: Vec<Arc<KvStore<C>>>,
+    snapshot_dir: Option<PathBuf>,
+    config: MaintenanceConfig,
+    on_error: Option<ErrorCallback>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl<C: Client + 'static> Maintenance<C> {
+    /// Create a new maintenance runner
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node to maintain
+    /// * `config` - The intervals of the routines
+    pub fn new(node: impl Into<NodeHandle<C>>, config: MaintenanceConfig) -> Self {
+        Self::for_nodes(vec![node.into()], config)
+    }
+
+    /// Create a new maintenance runner for several nodes
+    ///
+    /// # Arguments
+    ///
+    /// * `nodes` - The nodes to maintain
+    /// * `config` - The intervals of the routines
+    pub fn for_nodes(nodes: Vec<NodeHandle<C>>, config: MaintenanceConfig) -> Self {
+        Self {
+            nodes,
+            stores: Vec::new(),
+            snapshot_dir: None,
+            config,
+            on_error: None,
+            tasks: Vec::new(),
+        }
+    }
+
+    /// Add a key-value store whose replicas are synchronized and repaired by the runner
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The store to synchronize
+    pub fn with_store(mut self, store: Arc<KvStore<C>>) -> Self {
+        self.stores.push(store);
+        self
+    }
+
+    /// Save the routing state of every node to the given directory
+    ///
+    /// The snapshot of a node is written to the path given by
+    /// [`RoutingSnapshot::path_in`](crate::RoutingSnapshot::path_in), so it can be restored with
+    /// [`NodeService::restore_routing`](crate::NodeService::restore_routing) after a restart.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory of the snapshots
+    pub fn with_snapshot_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.snapshot_dir = Some(dir.into());
+        self
+    }
+
+    /// Set the callback called with every error returned by a routine
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The callback to call
+    pub fn on_error<F>(mut self, callback: F) -> Self
+        where F: Fn(Routine, &ServiceError) + Send + Sync + 'static {
+        self.on_error = Some(Arc::new(callback));
+        self
+    }
+
+    /// Get the handles of the maintained nodes
+    pub fn nodes(&self) -> &[NodeHandle<C>] {
+        &self.nodes
+    }
+
+    /// Returns true if the routines are running
+    pub fn is_running(&self) -> bool {
+        !self.tasks.is_empty()
+    }
+
+    /// Start the routines
+    ///
+    /// Does nothing if the routines are already running.
+    ///
+    /// > **Note**
+    /// >
+    /// > This method has to be called within a tokio runtime.
+    pub fn start(&mut self) {
+        if self.is_running() {
+            return;
+        }
+
+        let jitter = self.config.jitter;
+        self.tasks = vec![
+            self.spawn(Routine::Stabilize, self.config.stabilize_interval, jitter, |node| async move {
+                node.stabilize().await
+            }),
+            self.spawn(Routine::FixFingers, self.config.fix_fingers_interval, jitter, |node| async move {
+                node.fix_fingers().await
+            }),
+            self.spawn(Routine::CheckPredecessor, self.config.check_predecessor_interval, jitter, |node| async move {
+                node.check_predecessor().await
+            }),
+        ];
+
+        if let Some(dir) = self.snapshot_dir.clone() {
+            let task = self.spawn(Routine::SaveRouting, self.config.snapshot_interval, jitter, move |node| {
+                let path = RoutingSnapshot::path_in(&dir, node.node().id());
+                async move { node.save_routing(&path).map_err(ServiceError::from) }
+            });
+            self.tasks.push(task);
+        }
+
+        if !self.stores.is_empty() {
+            let task = Self::spawn_for(self.stores.clone(), |store| store.node().node().id(), self.on_error.clone(),
+                                       Routine::SyncReplicas, self.config.sync_replicas_interval, jitter, |store| async move {
+                store.sync_replicas().await
+            });
+            self.tasks.push(task);
+
+            let task = Self::spawn_for(self.stores.clone(), |store| store.node().node().id(), self.on_error.clone(),
+                                       Routine::AntiEntropy, self.config.anti_entropy_interval, jitter, |store| async move {
+                store.anti_entropy().await
+            });
+            self.tasks.push(task);
+
+            let task = Self::spawn_for(self.stores.clone(), |store| store.node().node().id(), self.on_error.clone(),
+                                       Routine::ReplayHints, self.config.replay_hints_interval, jitter, |store| async move {
+                store.replay_hints().await
+            });
+            self.tasks.push(task);
+
+            let task = Self::spawn_for(self.stores.clone(), |store| store.node().node().id(), self.on_error.clone(),
+                                       Routine::CollectTombstones, self.config.collect_tombstones_interval, jitter, |store| async move {
+                store.collect_tombstones().map_err(ServiceError::from)
+            });
+            self.tasks.push(task);
+        }
+    }
+
+    /// Stop the routines
+    ///
+    /// A routine which is in progress is cancelled at its next `.await` point.
+    pub fn stop(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+    }
+
+    fn spawn<F, Fut>(&self, routine: Routine, interval: Duration, jitter: Duration, run: F) -> JoinHandle<()>
+        where F: Fn(NodeHandle<C>) -> Fut + Send + 'static,
+              Fut: Future<Output = Result<(), ServiceError>> + Send {
+        Self::spawn_for(self.nodes.clone(), |node| node.node().id(), self.on_error.clone(), routine, interval, jitter, run)
+    }
+
+    fn spawn_for<T, F, Fut>(targets: Vec<T>, id: fn(&T) -> u64, on_error: Option<ErrorCallback>, routine: Routine,
+                            interval: Duration, jitter: Duration, run: F) -> JoinHandle<()>
+        where T: Clone + Send + Sync + 'static,
+              F: Fn(T) -> Fut + Send + 'static,
+              Fut: Future<Output = Result<(), ServiceError>> + Send {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval + random_jitter(jitter)).await;
+
+                for target in &targets {
+                    if let Err(err) = run(target.clone()).await {
+                        log::warn!("Maintenance routine {} of node {} failed: {}", routine, id(target), err);
+                        if let Some(on_error) = &on_error {
+                            on_error(routine, &err);
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl<C: Client + 'static> Drop for Maintenance<C> {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn random_jitter(max: Duration) -> Duration {
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+
+    Duration::from_millis(rand::random_range(0..=max.as_millis() as u64))
+}
@@ -1,11 +1,29 @@
 use crate::Node;
 
+#[derive(Clone)]
 pub struct Finger {
     pub(crate) start: u64,
     pub node: Node,
+    /// Set when the node couldn't be reached during a lookup. Suspect fingers are skipped by the
+    /// routing until the finger is pointed to a node again.
+    pub(crate) suspect: bool,
 }
 
 impl Finger {
+    pub(crate) fn new(start: u64, node: Node) -> Self {
+        Self { start, node, suspect: false }
+    }
+
+    /// Point the finger to the given node and clear the suspect mark
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node the finger points to
+    pub(crate) fn point_to(&mut self, node: Node) {
+        self.node = node;
+        self.suspect = false;
+    }
+
     /// Generate a finger id for a given node id and finger index.
     /// The finger id is calculated using the following formula:
     /// ```text
@@ -17,12 +35,9 @@ impl Finger {
     ///
     /// # Arguments
     ///
+    /// * `size` - The number of bits of the identifier space (m)
     /// * `node_id` - The id of the node
     /// * `index` - The index of the finger
-    pub(crate) fn finger_id(node_id: u64, index: u8) -> u64 {
-        Self::sized_finger_id(64_u8, node_id, index)
-    }
-
     pub(crate) fn sized_finger_id(size: u8, node_id: u64, index: u8) -> u64 {
         if index == 0 {
             return node_id;
@@ -41,20 +56,17 @@ impl Finger {
     ///
     /// # Arguments
     ///
+    /// * `size` - The number of bits of the identifier space (m), which is also the number of fingers
     /// * `node` - The node which will fill the finger table.
-    ///            Usually it's the immediate successor of the node for which the finger table is being generated.
-    pub(crate) fn init_finger_table(node: Node) -> Vec<Self> {
-        Self::sized_finger_table(64, node)
-    }
-
-    fn sized_finger_table(size: u8, node: Node) -> Vec<Self> {
+    ///   Usually it's the immediate successor of the node for which the finger table is being generated.
+    pub(crate) fn sized_finger_table(size: u8, node: Node) -> Vec<Self> {
         let mut fingers = Vec::with_capacity(size as usize);
 
         // We start at 1 because the calculation of the finger id is based on the index
         // of the finger. The calculation assumes that the index starts at 1.
         for i in 1..(size + 1) {
             let finger_id = Self::sized_finger_id(size, node.id, i);
-            fingers.push(Finger { start: finger_id, node: node.clone() });
+            fingers.push(Finger::new(finger_id, node.clone()));
         }
 
         fingers
@@ -70,25 +82,25 @@ mod tests {
     fn it_should_generate_finger_id() {
         let node_id: u64 = 1;
 
-        assert_eq!(Finger::finger_id(node_id, 0), 1);
-        assert_eq!(Finger::finger_id(node_id, 1), 2);
-        assert_eq!(Finger::finger_id(node_id, 2), 3);
-        assert_eq!(Finger::finger_id(node_id, 3), 5);
-        assert_eq!(Finger::finger_id(node_id, 4), 9);
-        assert_eq!(Finger::finger_id(node_id, 5), 17);
-        assert_eq!(Finger::finger_id(node_id, 6), 33);
-        assert_eq!(Finger::finger_id(node_id, 7), 65);
-        assert_eq!(Finger::finger_id(node_id, 8), 129);
-        assert_eq!(Finger::finger_id(node_id, 9), 257);
-        assert_eq!(Finger::finger_id(node_id, 10), 513);
-        assert_eq!(Finger::finger_id(node_id, 11), 1025);
-        assert_eq!(Finger::finger_id(node_id, 12), 2049);
-        assert_eq!(Finger::finger_id(node_id, 13), 4097);
-        assert_eq!(Finger::finger_id(node_id, 14), 8193);
-        assert_eq!(Finger::finger_id(node_id, 15), 16385);
-        assert_eq!(Finger::finger_id(node_id, 32), 2147483649);
-        assert_eq!(Finger::finger_id(node_id, 64), 9223372036854775809);
-        assert_eq!(Finger::finger_id(node_id, 65), 1);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 0), 1);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 1), 2);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 2), 3);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 3), 5);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 4), 9);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 5), 17);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 6), 33);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 7), 65);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 8), 129);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 9), 257);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 10), 513);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 11), 1025);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 12), 2049);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 13), 4097);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 14), 8193);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 15), 16385);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 32), 2147483649);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 64), 9223372036854775809);
+        assert_eq!(Finger::sized_finger_id(64, node_id, 65), 1);
 
         const M: u8 = 6;
         assert_eq!(Finger::sized_finger_id(M, node_id, 0), 1);
@@ -105,7 +117,7 @@ mod tests {
     fn it_should_generate_finger_table() {
         let node = Node::with_id(1, SocketAddr::from(([127, 0, 0, 1], 42001)));
 
-        let fingers = Finger::init_finger_table(node.clone());
+        let fingers = Finger::sized_finger_table(64, node.clone());
 
         assert_eq!(fingers.len(), 64);
         assert_eq!(fingers[0].start, 2);
@@ -1,5 +1,7 @@
 pub(crate) mod store;
 
 mod finger;
+mod snapshot;
 
 pub(super) use finger::Finger;
+pub use snapshot::RoutingSnapshot;
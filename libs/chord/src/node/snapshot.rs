@@ -0,0 +1,174 @@
+use std::fs::File;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use crate::{Node, StorageError};
+
+const HEADER: &str = "chord-routing v1";
+
+/// The routing state of a node, saved to disk so a restarted node doesn't have to rediscover the
+/// ring from scratch
+///
+/// The snapshot is a text file with one entry per line:
+///
+/// ```text
+/// chord-routing v1
+/// node 8 127.0.0.1:42008
+/// predecessor 2 127.0.0.1:42002
+/// successor 16 127.0.0.1:42016
+/// successor 21 127.0.0.1:42021
+/// finger 0 16 127.0.0.1:42016
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoutingSnapshot {
+    pub node: Node,
+    pub predecessor: Option<Node>,
+    pub successor_list: Vec<Node>,
+    pub fingers: Vec<Node>,
+}
+
+impl RoutingSnapshot {
+    /// Get the path of the snapshot of the given node in the directory
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory of the snapshots
+    /// * `node_id` - The id of the node
+    pub fn path_in(dir: &Path, node_id: u64) -> PathBuf {
+        dir.join(format!("{}.routing", node_id))
+    }
+
+    /// Get the nodes of the snapshot other than the node itself, without duplicates
+    ///
+    /// The successors come first, followed by the predecessor and the fingers, so the nodes
+    /// closest to the node are tried first when it rejoins the ring.
+    pub fn peers(&self) -> Vec<Node> {
+        let mut peers: Vec<Node> = Vec::new();
+        let nodes = self.successor_list.iter().chain(&self.predecessor).chain(&self.fingers);
+        for node in nodes {
+            if *node != self.node && !peers.contains(node) {
+                peers.push(node.clone());
+            }
+        }
+
+        peers
+    }
+
+    /// Write the snapshot to the given path
+    ///
+    /// The snapshot is written next to the path and renamed once it's synced to disk, and the
+    /// rename is synced as well, so a crash never leaves a partially written snapshot behind.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the snapshot file
+    pub fn save(&self, path: &Path) -> Result<(), StorageError> {
+        let mut lines = vec![HEADER.to_string(), format!("node {}", entry(&self.node))];
+        lines.extend(self.predecessor.iter().map(|node| format!("predecessor {}", entry(node))));
+        lines.extend(self.successor_list.iter().map(|node| format!("successor {}", entry(node))));
+        lines.extend(self.fingers.iter().enumerate().map(|(index, node)| format!("finger {} {}", index, entry(node))));
+
+        let partial = path.with_extension("partial");
+        let mut file = File::create(&partial)?;
+        file.write_all((lines.join("\n") + "\n").as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&partial, path)?;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            File::open(dir)?.sync_all()?;
+        }
+
+        Ok(())
+    }
+
+    /// Read the snapshot from the given path
+    ///
+    /// Returns `None` if there is no snapshot at the path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the snapshot file
+    pub fn load(path: &Path) -> Result<Option<Self>, StorageError> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut lines = content.lines();
+        if lines.next() != Some(HEADER) {
+            return Err(StorageError::Corrupted(format!("{} is not a routing snapshot", path.display())));
+        }
+
+        let (mut node, mut predecessor, mut successor_list, mut fingers) = (None, None, Vec::new(), Vec::new());
+        for line in lines.filter(|line| !line.is_empty()) {
+            let corrupted = || StorageError::Corrupted(format!("Invalid line of {}: {}", path.display(), line));
+            let fields: Vec<&str> = line.split(' ').collect();
+            match fields.as_slice() {
+                ["node", id, addr] => node = Some(parse(id, addr).ok_or_else(corrupted)?),
+                ["predecessor", id, addr] => predecessor = Some(parse(id, addr).ok_or_else(corrupted)?),
+                ["successor", id, addr] => successor_list.push(parse(id, addr).ok_or_else(corrupted)?),
+                ["finger", index, id, addr] if index.parse() == Ok(fingers.len()) => {
+                    fingers.push(parse(id, addr).ok_or_else(corrupted)?);
+                }
+                _ => return Err(corrupted()),
+            }
+        }
+
+        let node = node.ok_or_else(|| StorageError::Corrupted(format!("{} has no node", path.display())))?;
+        Ok(Some(Self { node, predecessor, successor_list, fingers }))
+    }
+}
+
+fn entry(node: &Node) -> String {
+    format!("{} {}", node.id(), node.addr())
+}
+
+fn parse(id: &str, addr: &str) -> Option<Node> {
+    Some(Node::with_id(id.parse().ok()?, addr.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use super::*;
+
+    fn node(id: u64) -> Node {
+        Node::with_id(id, SocketAddr::from(([127, 0, 0, 1], 42000 + id as u16)))
+    }
+
+    fn path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("chord-snapshot-{}-{}.routing", std::process::id(), name))
+    }
+
+    #[test]
+    fn it_should_save_and_load_snapshot() {
+        let path = path("save");
+        let snapshot = RoutingSnapshot {
+            node: node(8),
+            predecessor: Some(node(2)),
+            successor_list: vec![node(16), node(21)],
+            fingers: vec![node(16), node(16), node(21), node(2)],
+        };
+
+        snapshot.save(&path).unwrap();
+        let loaded = RoutingSnapshot::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, Some(snapshot.clone()));
+        assert_eq!(snapshot.peers(), vec![node(16), node(21), node(2)]);
+    }
+
+    #[test]
+    fn missing_snapshot_should_not_be_an_error() {
+        assert_eq!(RoutingSnapshot::load(&path("missing")).unwrap(), None);
+    }
+
+    #[test]
+    fn it_should_reject_invalid_snapshot() {
+        let path = path("invalid");
+        std::fs::write(&path, format!("{}\nnode 8 127.0.0.1:42008\nfinger 1 16 127.0.0.1:42016\n", HEADER)).unwrap();
+        let result = RoutingSnapshot::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(StorageError::Corrupted(_))));
+    }
+}
@@ -1,12 +1,24 @@
-use crate::Node;
-use crate::node::Finger;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use crate::{Config, Node};
+use crate::node::{Finger, RoutingSnapshot};
 
 /// A node in the chord ring
 ///
 /// This struct is used to represent a node in the chord ring.
+///
+/// The store is synchronized internally, so it can be shared between the tasks serving requests
+/// and the maintenance tasks. The finger table and the successor list are kept behind a single
+/// lock, because the successor is stored in both of them.
 pub struct NodeStore {
-    predecessor: Option<Node>,
-    pub(crate) finger_table: Vec<Finger>,
+    predecessor: RwLock<Option<Node>>,
+    routing: RwLock<Routing>,
+    successor_list_size: usize,
+}
+
+struct Routing {
+    finger_table: Vec<Finger>,
+    successor_list: Vec<Node>,
+    next_finger: usize,
 }
 
 impl NodeStore {
@@ -15,10 +27,18 @@ impl NodeStore {
     /// # Arguments
     ///
     /// * `successor` - The immediate successor of the current node 
-    pub(crate) fn new(successor: Node) -> Self {
+    /// * `config` - The node configuration
+    pub(crate) fn new(successor: Node, config: &Config) -> Self {
+        let routing = Routing {
+            finger_table: Finger::sized_finger_table(config.ring.bits, successor.clone()),
+            successor_list: vec![successor],
+            next_finger: 0,
+        };
+
         Self {
-            predecessor: None,
-            finger_table: Finger::init_finger_table(successor),
+            predecessor: RwLock::new(None),
+            routing: RwLock::new(routing),
+            successor_list_size: config.successor_list_size,
         }
     }
 
@@ -27,18 +47,52 @@ impl NodeStore {
     /// # Arguments
     ///
     /// * `predecessor` - The predecessor node
-    pub(crate) fn set_predecessor(&mut self, predecessor: Node) {
-        self.predecessor = Some(predecessor);
+    #[cfg(test)]
+    pub(crate) fn set_predecessor(&self, predecessor: Node) {
+        *self.write_predecessor() = Some(predecessor);
+    }
+
+    /// Set the predecessor of the node if the condition holds for the current predecessor
+    ///
+    /// The check and the update are done under the same lock, so the predecessor can't change in
+    /// between. Returns `true` if the predecessor was set.
+    ///
+    /// # Arguments
+    ///
+    /// * `predecessor` - The predecessor node
+    /// * `condition` - The condition to check against the current predecessor
+    pub(crate) fn set_predecessor_if<F>(&self, predecessor: Node, condition: F) -> bool
+        where F: FnOnce(Option<&Node>) -> bool {
+        let mut current = self.write_predecessor();
+        if condition(current.as_ref()) {
+            *current = Some(predecessor);
+            return true;
+        }
+
+        false
     }
 
     /// Unset the predecessor of the node
-    pub(crate) fn unset_predecessor(&mut self) {
-        self.predecessor = None;
+    #[cfg(test)]
+    pub(crate) fn unset_predecessor(&self) {
+        *self.write_predecessor() = None;
+    }
+
+    /// Unset the predecessor of the node if it's still the given node
+    ///
+    /// # Arguments
+    ///
+    /// * `predecessor` - The expected current predecessor
+    pub(crate) fn unset_predecessor_if(&self, predecessor: &Node) {
+        let mut current = self.write_predecessor();
+        if current.as_ref() == Some(predecessor) {
+            *current = None;
+        }
     }
 
     /// Get the predecessor of the node
-    pub(crate) fn predecessor(&self) -> Option<&Node> {
-        self.predecessor.as_ref()
+    pub(crate) fn predecessor(&self) -> Option<Node> {
+        self.predecessor.read().unwrap_or_else(PoisonError::into_inner).clone()
     }
 
     /// Set the successor of the node
@@ -46,13 +100,239 @@ impl NodeStore {
     /// # Arguments
     ///
     /// * `successor` - The successor node
-    pub(crate) fn set_successor(&mut self, successor: Node) {
-        self.finger_table[0].node = successor;
+    pub(crate) fn set_successor(&self, successor: Node) {
+        let mut routing = self.write_routing();
+        if routing.successor_list.first() != Some(&successor) {
+            routing.successor_list.insert(0, successor.clone());
+            routing.successor_list.truncate(self.successor_list_size);
+        }
+        routing.finger_table[0].point_to(successor);
+    }
+
+    /// Set the successor of the node if the condition holds for the current successor
+    ///
+    /// Returns `true` if the successor was set.
+    ///
+    /// # Arguments
+    ///
+    /// * `successor` - The successor node
+    /// * `condition` - The condition to check against the current successor
+    pub(crate) fn set_successor_if<F>(&self, successor: Node, condition: F) -> bool
+        where F: FnOnce(&Node) -> bool {
+        let mut routing = self.write_routing();
+        if !condition(&routing.finger_table[0].node) {
+            return false;
+        }
+
+        if routing.successor_list.first() != Some(&successor) {
+            routing.successor_list.insert(0, successor.clone());
+            routing.successor_list.truncate(self.successor_list_size);
+        }
+        routing.finger_table[0].point_to(successor);
+
+        true
     }
 
     /// Get the successor of the node
-    pub(crate) fn successor(&self) -> &Node {
-        &self.finger_table[0].node
+    pub(crate) fn successor(&self) -> Node {
+        self.read_routing().finger_table[0].node.clone()
+    }
+
+    /// Get the successor list of the node
+    ///
+    /// The first entry of the list is always the immediate successor.
+    pub(crate) fn successor_list(&self) -> Vec<Node> {
+        self.read_routing().successor_list.clone()
+    }
+
+    /// Update the successor list with the list retrieved from the successor
+    ///
+    /// The immediate successor is kept at the head of the list, followed by the successor's own
+    /// successors. The list is truncated to the configured size.
+    ///
+    /// # Arguments
+    ///
+    /// * `successors` - The successor list of the immediate successor
+    pub(crate) fn update_successor_list(&self, successors: Vec<Node>) {
+        let mut routing = self.write_routing();
+        let mut list = Vec::with_capacity(self.successor_list_size);
+        list.push(routing.finger_table[0].node.clone());
+        list.extend(successors);
+        list.truncate(self.successor_list_size);
+
+        routing.successor_list = list;
+    }
+
+    /// Replace all references to a node which left the ring
+    ///
+    /// Fingers pointing to the node are redirected to its successor, which takes over its range.
+    /// The node is replaced by its successor in the successor list as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node which left the ring
+    /// * `successor` - The successor of the node which left the ring
+    pub(crate) fn replace_node(&self, node: &Node, successor: &Node) {
+        let mut routing = self.write_routing();
+        for finger in routing.finger_table.iter_mut().filter(|f| &f.node == node) {
+            finger.point_to(successor.clone());
+        }
+
+        for entry in routing.successor_list.iter_mut().filter(|n| *n == node) {
+            *entry = successor.clone();
+        }
+        routing.successor_list.dedup();
+    }
+
+    /// Remove the failed successor and promote the next entry of the successor list
+    ///
+    /// Nothing is removed if the successor has already been replaced in the meantime.
+    ///
+    /// Returns the new successor, or `None` if there is no other successor to fall back to.
+    /// In that case the current successor is kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `failed` - The successor which failed
+    pub(crate) fn remove_successor(&self, failed: &Node) -> Option<Node> {
+        let mut routing = self.write_routing();
+        if &routing.finger_table[0].node != failed {
+            return Some(routing.finger_table[0].node.clone());
+        }
+
+        if routing.successor_list.len() < 2 {
+            return None;
+        }
+
+        routing.successor_list.remove(0);
+        let successor = routing.successor_list[0].clone();
+        routing.finger_table[0].point_to(successor.clone());
+
+        Some(successor)
+    }
+
+    /// Get a copy of the finger table
+    pub(crate) fn fingers(&self) -> Vec<Finger> {
+        self.read_routing().finger_table.clone()
+    }
+
+    /// Get the number of fingers in the finger table
+    pub(crate) fn finger_count(&self) -> usize {
+        self.read_routing().finger_table.len()
+    }
+
+    /// Set the node of the finger at the given index
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the finger in the finger table
+    /// * `node` - The node the finger points to
+    pub(crate) fn set_finger(&self, index: usize, node: Node) {
+        let mut routing = self.write_routing();
+        routing.finger_table[index].point_to(node);
+    }
+
+    /// Mark all the fingers pointing to the given node as suspect
+    ///
+    /// The marks are cleared once the fingers are pointed to a node again, e.g. by `fix_fingers`.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node which couldn't be reached
+    pub(crate) fn mark_suspect(&self, node: &Node) {
+        let mut routing = self.write_routing();
+        for finger in routing.finger_table.iter_mut().filter(|f| &f.node == node) {
+            finger.suspect = true;
+        }
+    }
+
+    /// Get the indexes of the next fingers to refresh and move the cursor past them
+    ///
+    /// The cursor wraps around at the end of the finger table.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - The number of fingers to return
+    pub(crate) fn next_fingers(&self, count: usize) -> Vec<usize> {
+        let mut routing = self.write_routing();
+        let len = routing.finger_table.len();
+        let indexes = (0..count.min(len))
+            .map(|i| (routing.next_finger + i) % len)
+            .collect();
+        routing.next_finger = (routing.next_finger + count) % len;
+
+        indexes
+    }
+
+    /// Get the routing state of the node
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The current node
+    pub(crate) fn snapshot(&self, node: Node) -> RoutingSnapshot {
+        let predecessor = self.predecessor();
+        let routing = self.read_routing();
+        RoutingSnapshot {
+            node,
+            predecessor,
+            successor_list: routing.successor_list.clone(),
+            fingers: routing.finger_table.iter().map(|finger| finger.node.clone()).collect(),
+        }
+    }
+
+    /// Restore the routing state of a snapshot, keeping only the nodes which are alive
+    ///
+    /// The entries pointing to dead nodes are left as they are. The successor is only replaced
+    /// when one of the saved successors is alive.
+    ///
+    /// # Arguments
+    ///
+    /// * `snapshot` - The saved routing state
+    /// * `alive` - Returns true if the node is alive
+    pub(crate) fn restore<F>(&self, snapshot: &RoutingSnapshot, alive: F)
+        where F: Fn(&Node) -> bool {
+        let mut routing = self.write_routing();
+        let successors: Vec<Node> = snapshot.successor_list.iter()
+            .filter(|node| alive(node))
+            .take(self.successor_list_size)
+            .cloned()
+            .collect();
+        if let Some(successor) = successors.first() {
+            routing.finger_table[0].point_to(successor.clone());
+            routing.successor_list = successors;
+        }
+
+        // The first finger is the successor
+        for (finger, node) in routing.finger_table.iter_mut().zip(&snapshot.fingers).skip(1) {
+            if alive(node) {
+                finger.point_to(node.clone());
+            }
+        }
+        drop(routing);
+
+        if let Some(predecessor) = snapshot.predecessor.iter().find(|node| alive(node)) {
+            *self.write_predecessor() = Some(predecessor.clone());
+        }
+    }
+
+    /// Replace the whole finger table
+    #[cfg(test)]
+    pub(crate) fn set_fingers(&self, fingers: Vec<Finger>) {
+        self.write_routing().finger_table = fingers;
+    }
+
+    // A panic while holding a lock can't leave the store half updated, every update is done
+    // with a single assignment. So we ignore the poisoning and grab the lock regardless.
+    fn read_routing(&self) -> RwLockReadGuard<'_, Routing> {
+        self.routing.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write_routing(&self) -> RwLockWriteGuard<'_, Routing> {
+        self.routing.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write_predecessor(&self) -> RwLockWriteGuard<'_, Option<Node>> {
+        self.predecessor.write().unwrap_or_else(PoisonError::into_inner)
     }
 }
 
@@ -65,21 +345,21 @@ mod tests {
     #[test]
     fn test_new() {
         let node = Node::with_id(1, SocketAddr::from(([127, 0, 0, 1], 42001)));
-        let store = NodeStore::new(node.clone());
+        let store = NodeStore::new(node.clone(), &Config::default());
 
-        assert_eq!(store.successor(), &node);
+        assert_eq!(store.successor(), node);
         assert_eq!(store.predecessor(), None);
     }
 
     #[test]
     fn test_predecessor() {
         let node = Node::with_id(1, SocketAddr::from(([127, 0, 0, 1], 42001)));
-        let mut store = NodeStore::new(node.clone());
+        let store = NodeStore::new(node.clone(), &Config::default());
         let predecessor = Node::with_id(2, SocketAddr::from(([127, 0, 0, 1], 42002)));
         assert_eq!(store.predecessor(), None);
         store.set_predecessor(predecessor.clone());
 
-        assert_eq!(store.predecessor(), Some(&predecessor));
+        assert_eq!(store.predecessor(), Some(predecessor));
 
         store.unset_predecessor();
         assert_eq!(store.predecessor(), None);
@@ -88,11 +368,85 @@ mod tests {
     #[test]
     fn test_successor() {
         let node = Node::with_id(1, SocketAddr::from(([127, 0, 0, 1], 42001)));
-        let mut store = NodeStore::new(node.clone());
+        let store = NodeStore::new(node.clone(), &Config::default());
         let successor = Node::with_id(2, SocketAddr::from(([127, 0, 0, 1], 42002)));
-        assert_eq!(store.successor(), &node);
+        assert_eq!(store.successor(), node);
         store.set_successor(successor.clone());
 
-        assert_eq!(store.successor(), &successor);
+        assert_eq!(store.successor(), successor);
+        assert_eq!(store.successor_list(), vec![successor, node]);
+    }
+
+    #[test]
+    fn test_successor_list() {
+        let node = Node::with_id(1, SocketAddr::from(([127, 0, 0, 1], 42001)));
+        let store = NodeStore::new(node.clone(), &Config::default());
+        let successor = Node::with_id(2, SocketAddr::from(([127, 0, 0, 1], 42002)));
+        let second = Node::with_id(3, SocketAddr::from(([127, 0, 0, 1], 42003)));
+        let third = Node::with_id(4, SocketAddr::from(([127, 0, 0, 1], 42004)));
+        store.set_successor(successor.clone());
+
+        store.update_successor_list(vec![second.clone(), third.clone(), node.clone()]);
+        assert_eq!(store.successor_list(), vec![successor.clone(), second.clone(), third.clone()]);
+
+        assert_eq!(store.remove_successor(&successor), Some(second.clone()));
+        assert_eq!(store.successor(), second);
+        assert_eq!(store.successor_list(), vec![second.clone(), third.clone()]);
+
+        assert_eq!(store.remove_successor(&successor), Some(second.clone()));
+        assert_eq!(store.remove_successor(&second), Some(third.clone()));
+        assert_eq!(store.remove_successor(&third), None);
+        assert_eq!(store.successor(), third);
+    }
+
+    #[test]
+    fn test_next_fingers() {
+        let node = Node::with_id(1, SocketAddr::from(([127, 0, 0, 1], 42001)));
+        let store = NodeStore::new(node, &Config::default());
+
+        assert_eq!(store.next_fingers(1), vec![0]);
+        assert_eq!(store.next_fingers(3), vec![1, 2, 3]);
+        assert_eq!(store.next_fingers(60), (4..64).collect::<Vec<usize>>());
+        assert_eq!(store.next_fingers(2), vec![0, 1]);
+        assert_eq!(store.next_fingers(65).len(), 64);
+        assert_eq!(store.next_fingers(1), vec![3]);
+    }
+
+    #[test]
+    fn test_mark_suspect() {
+        let node = Node::with_id(1, SocketAddr::from(([127, 0, 0, 1], 42001)));
+        let store = NodeStore::new(node.clone(), &Config::default());
+        let other = Node::with_id(2, SocketAddr::from(([127, 0, 0, 1], 42002)));
+        store.set_finger(1, other.clone());
+
+        store.mark_suspect(&node);
+        let fingers = store.fingers();
+        assert!(fingers[0].suspect);
+        assert!(!fingers[1].suspect);
+        assert!(fingers[2..].iter().all(|f| f.suspect));
+
+        store.set_finger(2, other);
+        store.set_successor(node);
+        let fingers = store.fingers();
+        assert!(!fingers[0].suspect);
+        assert!(!fingers[2].suspect);
+        assert!(fingers[3].suspect);
+    }
+
+    #[test]
+    fn test_replace_node() {
+        let node = Node::with_id(1, SocketAddr::from(([127, 0, 0, 1], 42001)));
+        let store = NodeStore::new(node.clone(), &Config::default());
+        let leaving = Node::with_id(2, SocketAddr::from(([127, 0, 0, 1], 42002)));
+        let successor = Node::with_id(3, SocketAddr::from(([127, 0, 0, 1], 42003)));
+        let third = Node::with_id(4, SocketAddr::from(([127, 0, 0, 1], 42004)));
+        store.set_successor(leaving.clone());
+        store.update_successor_list(vec![successor.clone(), third.clone()]);
+
+        store.replace_node(&leaving, &successor);
+
+        assert_eq!(store.successor(), successor);
+        assert_eq!(store.successor_list(), vec![successor.clone(), third]);
+        assert!(store.fingers().iter().all(|f| f.node != leaving));
     }
 }
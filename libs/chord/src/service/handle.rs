@@ -0,0 +1,60 @@
+use std::ops::Deref;
+use std::sync::Arc;
+use crate::{Client, NodeService};
+
+/// A cloneable handle to a node service
+///
+/// All clones of the handle point to the same node. The node state is synchronized internally,
+/// so an RPC server can serve `notify` and `find_successor` requests while the maintenance
+/// routines are running, without a global lock around the service.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn run<C: chord_rs::Client + 'static>(service: chord_rs::NodeService<C>) {
+/// use chord_rs::NodeHandle;
+///
+/// let handle = NodeHandle::new(service);
+///
+/// let maintenance = handle.clone();
+/// tokio::spawn(async move {
+///     maintenance.fix_fingers().await.unwrap();
+/// });
+///
+/// let successor = handle.find_successor(42).await;
+/// # }
+/// ```
+pub struct NodeHandle<C: Client> {
+    service: Arc<NodeService<C>>,
+}
+
+impl<C: Client> NodeHandle<C> {
+    /// Create a new handle owning the given service
+    ///
+    /// # Arguments
+    ///
+    /// * `service` - The node service to share
+    pub fn new(service: NodeService<C>) -> Self {
+        Self { service: Arc::new(service) }
+    }
+}
+
+impl<C: Client> Clone for NodeHandle<C> {
+    fn clone(&self) -> Self {
+        Self { service: self.service.clone() }
+    }
+}
+
+impl<C: Client> Deref for NodeHandle<C> {
+    type Target = NodeService<C>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.service
+    }
+}
+
+impl<C: Client> From<NodeService<C>> for NodeHandle<C> {
+    fn from(service: NodeService<C>) -> Self {
+        Self::new(service)
+    }
+}
@@ -0,0 +1,176 @@
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use crate::{Client, Node, NodeService};
+use crate::client::ClientError;
+use crate::service::error::ServiceError;
+
+/// Default maximum number of hops of an iterative lookup
+pub const DEFAULT_MAX_HOPS: usize = 64;
+
+/// Default time to wait for a single hop of an iterative lookup
+pub const DEFAULT_HOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The way a lookup is routed through the ring
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LookupMode {
+    /// The query is forwarded from node to node and only the owner comes back to the originator.
+    ///
+    /// The originator doesn't see the hops after the first forwarded one, so a
+    /// [`LookupTrace`] of the whole path requires [`LookupMode::Iterative`].
+    Recursive,
+    /// The originator asks each hop for its closest preceding finger and drives the next hop
+    /// itself.
+    Iterative {
+        /// The maximum number of hops before the lookup is aborted
+        max_hops: usize,
+        /// The maximum time to wait for a single hop
+        hop_timeout: Duration,
+    },
+}
+
+impl LookupMode {
+    /// Iterative lookup with the default hop limit and timeout
+    pub fn iterative() -> Self {
+        Self::Iterative {
+            max_hops: DEFAULT_MAX_HOPS,
+            hop_timeout: DEFAULT_HOP_TIMEOUT,
+        }
+    }
+}
+
+/// A single hop of a lookup
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hop {
+    /// The id of the node which was asked
+    pub id: u64,
+    /// The address of the node which was asked
+    pub addr: SocketAddr,
+    /// The time it took the node to respond
+    pub elapsed: Duration,
+}
+
+impl Hop {
+    fn new(node: &Node, elapsed: Duration) -> Self {
+        Self { id: node.id, addr: node.addr, elapsed }
+    }
+}
+
+/// The result of a lookup together with the path it took through the ring
+#[derive(Clone, Debug)]
+pub struct LookupTrace {
+    /// The node owning the id
+    pub owner: Node,
+    /// The ordered list of hops. The first hop is always the node which started the lookup.
+    pub hops: Vec<Hop>,
+}
+
+impl<C: Client + 'static> NodeService<C> {
+    /// Find the successor of the given id using the given lookup mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id to find the successor for
+    /// * `mode` - The way the lookup is routed through the ring
+    pub async fn lookup(&self, id: u64, mode: LookupMode) -> Result<Node, ServiceError> {
+        self.lookup_with_trace(id, mode).await.map(|trace| trace.owner)
+    }
+
+    /// Find the successor of the given id and record the hops the lookup went through.
+    ///
+    /// Only the iterative mode records every hop of the lookup, so it's the one to use to see the
+    /// path of a lookup through the ring, e.g. to check that it takes O(log N) hops. In the
+    /// recursive mode only the first forwarded hop is visible to the originator, so the trace
+    /// contains at most 2 hops however long the path is.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id to find the successor for. It's masked to the identifier space of the ring.
+    /// * `mode` - The way the lookup is routed through the ring
+    pub async fn lookup_with_trace(&self, id: u64, mode: LookupMode) -> Result<LookupTrace, ServiceError> {
+        let id = self.config.ring.mask(id);
+        match mode {
+            LookupMode::Recursive => self.recursive_lookup(id).await,
+            LookupMode::Iterative { max_hops, hop_timeout } => self.iterative_lookup(id, max_hops, hop_timeout).await,
+        }
+    }
+
+    async fn recursive_lookup(&self, id: u64) -> Result<LookupTrace, ServiceError> {
+        let start = Instant::now();
+        let successor = self.store.successor();
+        if Node::is_between_on_ring(id, self.id, successor.id) {
+            let hops = vec![Hop::new(&self.node(), start.elapsed())];
+            return Ok(LookupTrace { owner: successor, hops });
+        }
+
+        let mut hops = vec![Hop::new(&self.node(), start.elapsed())];
+
+        let start = Instant::now();
+        let (n, owner) = self.forward_find_successor(id).await?;
+        hops.push(Hop::new(&n, start.elapsed()));
+
+        Ok(LookupTrace { owner, hops })
+    }
+
+    /// Find the successor of the given id by asking each hop for its closest preceding node.
+    ///
+    /// The first hop is resolved locally. Each following hop costs 2 requests: one for the
+    /// successor of the hop and one for its closest preceding node.
+    async fn iterative_lookup(&self, id: u64, max_hops: usize, hop_timeout: Duration) -> Result<LookupTrace, ServiceError> {
+        let start = Instant::now();
+        let successor = self.store.successor();
+        if Node::is_between_on_ring(id, self.id, successor.id) {
+            let hops = vec![Hop::new(&self.node(), start.elapsed())];
+            return Ok(LookupTrace { owner: successor, hops });
+        }
+
+        let mut hop = self.closest_preceding_node(id);
+        let mut hops = vec![Hop::new(&self.node(), start.elapsed())];
+        for _ in 0..max_hops {
+            let start = Instant::now();
+            let successor = Self::call_with_timeout(&hop, hop_timeout, |client| async move {
+                client.successor().await
+            }).await?;
+            if Node::is_between_on_ring(id, hop.id, successor.id) {
+                hops.push(Hop::new(&hop, start.elapsed()));
+                return Ok(LookupTrace { owner: successor, hops });
+            }
+
+            let next = Self::call_with_timeout(&hop, hop_timeout, move |client| async move {
+                client.closest_preceding_node(id).await
+            }).await?;
+            hops.push(Hop::new(&hop, start.elapsed()));
+            if next == hop {
+                // The hop has no finger closer to the id, so its successor is the owner
+                return Ok(LookupTrace { owner: successor, hops });
+            }
+            hop = next;
+        }
+
+        Err(ServiceError::HopLimitExceeded(id, max_hops))
+    }
+
+    /// Call the given node and wait at most `timeout` for the response.
+    ///
+    /// The request runs on a separate task, so a client blocking its thread can't hold the lookup
+    /// past the timeout. When the timeout is reached, the task is left running until the client
+    /// returns.
+    async fn call_with_timeout<T, F, Fut>(node: &Node, timeout: Duration, request: F) -> Result<T, ServiceError>
+        where T: Send + 'static,
+              F: FnOnce(C) -> Fut + Send + 'static,
+              Fut: Future<Output = Result<T, ClientError>> + Send {
+        let target = node.clone();
+        let task = tokio::spawn(async move {
+            let client: C = target.client();
+            request(client).await
+        });
+
+        match tokio::time::timeout(timeout, task).await {
+            Ok(Ok(result)) => Ok(result?),
+            Ok(Err(err)) => Err(ServiceError::Unexpected(
+                format!("Request to node {} failed: {}", node.addr(), err)
+            )),
+            Err(_) => Err(ServiceError::Timeout(node.clone())),
+        }
+    }
+}
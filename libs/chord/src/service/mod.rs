@@ -1,38 +1,81 @@
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
+mod handle;
+mod lookup;
+
+pub use handle::NodeHandle;
+pub use lookup::{Hop, LookupMode, LookupTrace};
 
 use std::marker::PhantomData;
 use std::net::SocketAddr;
-use seahash::hash;
-use crate::{Client, Node};
+use std::path::Path;
+use tokio::task::JoinSet;
+use crate::{Client, Config, FixFingersMode, Node, StorageError};
 use crate::client::ClientError;
-use crate::node::Finger;
+use crate::node::{Finger, RoutingSnapshot};
 use crate::node::store::NodeStore;
 
 pub struct NodeService<C: Client> {
     id: u64,
     addr: SocketAddr,
     store: NodeStore,
+    config: Config,
     phantom: PhantomData<C>,
 }
 
 
 impl<C: Client> NodeService<C> {
     pub fn new(socket_addr: SocketAddr) -> Self {
-        let id = hash(socket_addr.ip().to_string().as_bytes());
-        Self::with_id(id, socket_addr)
+        Self::with_config(socket_addr, Config::default())
+    }
+
+    /// Create a new node service with the given configuration.
+    ///
+    /// The id of the node is derived from its full address using the hasher of the ring, the same
+    /// way as [`Node::with_ring`] does.
+    ///
+    /// # Arguments
+    ///
+    /// * `socket_addr` - The address of the node
+    /// * `config` - The node configuration
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is not valid, see [`Config::validate`].
+    pub fn with_config(socket_addr: SocketAddr, config: Config) -> Self {
+        let id = Node::with_ring(socket_addr, &config.ring).id;
+        Self::with_id_and_config(id, socket_addr, config)
     }
 
+    #[cfg(test)]
     fn with_id(id: u64, addr: SocketAddr) -> Self {
-        let store = NodeStore::new(Node::with_id(id, addr));
+        Self::with_id_and_config(id, addr, Config::default())
+    }
+
+    pub(crate) fn with_id_and_config(id: u64, addr: SocketAddr, config: Config) -> Self {
+        if let Err(err) = config.validate() {
+            panic!("Invalid configuration: {}", err);
+        }
+        let store = NodeStore::new(Node::with_id(id, addr), &config);
         Self {
             id,
             addr,
             store,
+            config,
             phantom: PhantomData,
         }
     }
 
+    /// Get the reference to the current node
+    pub fn node(&self) -> Node {
+        Node::with_id(self.id, self.addr)
+    }
+
+    /// Get the configuration of the node
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
     /// Find the successor of the given id.
     ///
     /// If the given id is in the range of the current node and its successor, the successor is returned.
@@ -40,18 +83,44 @@ impl<C: Client> NodeService<C> {
     ///
     /// # Arguments
     ///
-    /// * `id` - The id to find the successor for
-    pub fn find_successor(&self, id: u64) -> Result<Node, error::ServiceError> {
-        if Node::is_between_on_ring(id, self.id, self.store.successor().id) {
-            Ok(self.store.successor().clone())
+    /// * `id` - The id to find the successor for. It's masked to the identifier space of the ring.
+    pub async fn find_successor(&self, id: u64) -> Result<Node, error::ServiceError> {
+        let id = self.config.ring.mask(id);
+        let successor = self.store.successor();
+        if Node::is_between_on_ring(id, self.id, successor.id) {
+            Ok(successor)
         } else {
-            let n = self.closest_preceding_node(id);
-            let client: C = n.client();
-            let successor = client.find_successor(id)?;
+            let (_, successor) = self.forward_find_successor(id).await?;
             Ok(successor)
         }
     }
 
+    /// Ask the closest preceding node of the given id for its successor.
+    ///
+    /// If the node can't be reached, it's marked as suspect and the next best node is asked
+    /// instead, falling back to the successor list as the last resort. Returns the node which
+    /// answered, together with the successor.
+    async fn forward_find_successor(&self, id: u64) -> Result<(Node, Node), error::ServiceError> {
+        let mut failure = None;
+        for node in self.preceding_nodes(id) {
+            let client: C = node.client();
+            match client.find_successor(id).await {
+                Ok(successor) => return Ok((node, successor)),
+                Err(err @ (ClientError::ConnectionFailed(_) | ClientError::Timeout(_))) => {
+                    log::debug!("Node {} failed during lookup of id {}: {}", node.id, id, err);
+                    self.store.mark_suspect(&node);
+                    failure = Some(err);
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Err(match failure {
+            Some(err) => err.into(),
+            None => error::ServiceError::Unreachable(self.store.successor()),
+        })
+    }
+
     /// Join the chord ring.
     ///
     /// This method is used to join the chord ring. It will find the successor of its own id
@@ -60,14 +129,171 @@ impl<C: Client> NodeService<C> {
     /// # Arguments
     ///
     /// * `node` - The node to join the ring with. It's an existing node in the ring.
-    pub fn join(&mut self, node: Node) -> Result<(), error::ServiceError> {
+    pub async fn join(&self, node: Node) -> Result<(), error::ServiceError> {
         let client: C = node.client();
-        let successor = client.find_successor(self.id)?;
+        let successor = client.find_successor(self.id).await?;
         self.store.set_successor(successor);
 
         Ok(())
     }
 
+    /// Leave the chord ring.
+    ///
+    /// This method is used to leave the ring gracefully. The predecessor is told about the
+    /// successor and the successor is told about the predecessor, so both of them can rewire
+    /// right away instead of waiting for the failure to be detected.
+    ///
+    /// > **Note**
+    /// >
+    /// > The node should not be used after leaving the ring.
+    pub async fn leave(&self) -> Result<(), error::ServiceError> {
+        let node = self.node();
+        let successor = self.store.successor();
+        if successor == node {
+            // The node is the only one in the ring, there is nobody to notify
+            return Ok(());
+        }
+
+        let predecessor = self.store.predecessor();
+        if let Some(predecessor) = &predecessor {
+            let client: C = predecessor.client();
+            client.successor_leaving(node.clone(), successor.clone()).await?;
+        }
+
+        let client: C = successor.client();
+        client.predecessor_leaving(node, predecessor).await?;
+
+        Ok(())
+    }
+
+    /// Save the routing state of the node, its predecessor, successor list and fingers, to the
+    /// given path.
+    ///
+    /// > **Note**
+    /// >
+    /// > This method should be called periodically, see
+    /// > [`Maintenance::with_snapshot_dir`](crate::Maintenance::with_snapshot_dir).
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the snapshot file
+    pub fn save_routing(&self, path: &Path) -> Result<(), StorageError> {
+        self.store.snapshot(self.node()).save(path)
+    }
+
+    /// Restore the routing state saved with [`NodeService::save_routing`] and rejoin the ring.
+    ///
+    /// All the saved peers are pinged at once. The entries pointing to the peers which answered
+    /// are restored, and the node rejoins the ring through the first of them, trying the
+    /// successors first. The routing state of the live peers lets the node route right away,
+    /// while `stabilize` and `fix_fingers` catch up with the changes made during the downtime.
+    ///
+    /// Returns the peer the node rejoined the ring through, or `None` if there is no snapshot at
+    /// the path or none of the saved peers is alive. The node has to join the ring through a
+    /// known node then.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the snapshot file
+    pub async fn restore_routing(&self, path: &Path) -> Result<Option<Node>, error::ServiceError>
+        where C: 'static {
+        let snapshot = match RoutingSnapshot::load(path)? {
+            Some(snapshot) => snapshot,
+            None => return Ok(None),
+        };
+        if snapshot.node != self.node() {
+            let message = format!("{} is a snapshot of node {}, not {}", path.display(), snapshot.node.id, self.id);
+            return Err(StorageError::Corrupted(message).into());
+        }
+
+        let peers = snapshot.peers();
+        let mut pings = JoinSet::new();
+        for (index, peer) in peers.iter().enumerate() {
+            let client: C = peer.client();
+            pings.spawn(async move { (index, client.ping().await) });
+        }
+
+        let mut alive = vec![false; peers.len()];
+        while let Some(result) = pings.join_next().await {
+            match result {
+                Ok((index, Ok(()))) => alive[index] = true,
+                Ok((index, Err(err))) => log::debug!("Saved peer {} is not alive: {}", peers[index].id, err),
+                Err(err) => log::debug!("Ping of a saved peer failed: {}", err),
+            }
+        }
+
+        let alive: Vec<Node> = peers.into_iter().zip(alive).filter(|(_, alive)| *alive).map(|(peer, _)| peer).collect();
+        self.store.restore(&snapshot, |node| alive.contains(node));
+        log::debug!("Node {} restored its routing state with {} live peers", self.id, alive.len());
+
+        for peer in alive {
+            match self.join(peer.clone()).await {
+                Ok(()) => return Ok(Some(peer)),
+                Err(err) => log::debug!("Rejoining the ring through node {} failed: {}", peer.id, err),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Handle the successor leaving the ring.
+    ///
+    /// All references to the leaving node are replaced by its successor.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaving` - The node which is leaving the ring
+    /// * `successor` - The successor of the leaving node
+    pub fn successor_leaving(&self, leaving: Node, successor: Node) {
+        self.store.replace_node(&leaving, &successor);
+    }
+
+    /// Handle the predecessor leaving the ring.
+    ///
+    /// If the leaving node is the current predecessor, the predecessor is set to the predecessor
+    /// of the leaving node.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaving` - The node which is leaving the ring
+    /// * `predecessor` - The predecessor of the leaving node
+    pub fn predecessor_leaving(&self, leaving: Node, predecessor: Option<Node>) {
+        match predecessor {
+            Some(predecessor) if predecessor.id != self.id => {
+                self.store.set_predecessor_if(predecessor, |current| current == Some(&leaving));
+            }
+            _ => self.store.unset_predecessor_if(&leaving),
+        }
+    }
+
+    /// Get the successor list of the node.
+    ///
+    /// The first entry of the list is the immediate successor.
+    pub fn successor_list(&self) -> Vec<Node> {
+        self.store.successor_list()
+    }
+
+    /// Get the predecessor of the node, if it's known.
+    pub fn predecessor(&self) -> Option<Node> {
+        self.store.predecessor()
+    }
+
+    /// Get the successors of the node running on distinct physical hosts.
+    ///
+    /// The successor list is filtered so that it contains at most one virtual node per host and
+    /// no virtual node of the current host. The order of the successor list is kept.
+    pub fn successor_hosts(&self) -> Vec<Node> {
+        let node = self.node();
+        let mut hosts: Vec<Node> = Vec::new();
+        for successor in self.store.successor_list() {
+            if !successor.is_same_host(&node) && !hosts.iter().any(|host| host.is_same_host(&successor)) {
+                hosts.push(successor);
+            }
+        }
+
+        hosts
+    }
+
     /// Notify the node about a potential new predecessor.
     ///
     /// If the predecessor is not set or the given node is in the range of the current node and the
@@ -76,11 +302,11 @@ impl<C: Client> NodeService<C> {
     /// # Arguments
     ///
     /// * `node` - The node which might be the new predecessor
-    pub fn notify(&mut self, node: Node) {
-        let predecessor = self.store.predecessor();
-        if predecessor.is_none() || Node::is_between_on_ring(node.id.clone(), predecessor.unwrap().id, self.id) {
-            self.store.set_predecessor(node);
-        }
+    pub fn notify(&self, node: Node) {
+        let id = self.id;
+        self.store.set_predecessor_if(node.clone(), |predecessor| {
+            predecessor.is_none() || Node::is_between_on_ring(node.id, predecessor.unwrap().id, id)
+        });
     }
 
     /// Stabilize the node
@@ -89,22 +315,41 @@ impl<C: Client> NodeService<C> {
     /// is in the range of the current node and its successor. If so, the successor will be set to
     /// the retrieved predecessor.
     ///
+    /// If the successor can't be reached, it is replaced by the next entry of the successor list.
+    /// Once a live successor is found, its successor list is used to refresh the local one.
+    ///
     /// It will also notify the successor about the current node.
     ///
     /// > **Note**
     /// >
-    /// > This method should be called periodically.
-    pub fn stabilize(&mut self) -> Result<(), error::ServiceError> {
-        let client: C = self.store.successor().client();
-        let result = client.predecessor();
-        if let Ok(Some(x)) = result {
-            if Node::is_between_on_ring(x.id.clone(), self.id, self.store.successor().id) {
-                self.store.set_successor(x);
+    /// > This method should be called periodically, see [`Maintenance`](crate::Maintenance).
+    pub async fn stabilize(&self) -> Result<(), error::ServiceError> {
+        let result = loop {
+            let successor = self.store.successor();
+            let client: C = successor.client();
+            let result = client.predecessor().await;
+            if let Err(ClientError::ConnectionFailed(_) | ClientError::Timeout(_)) = result {
+                if self.store.remove_successor(&successor).is_some() {
+                    continue;
+                }
             }
+
+            break result;
+        };
+
+        if let Ok(Some(x)) = result {
+            let id = self.id;
+            self.store.set_successor_if(x.clone(), |successor| {
+                Node::is_between_on_ring(x.id, id, successor.id)
+            });
         }
 
         let client: C = self.store.successor().client();
-        client.notify(Node { id: self.id, addr: self.addr })?;
+        if let Ok(successors) = client.successor_list().await {
+            self.store.update_successor_list(successors);
+        }
+
+        client.notify(self.node()).await?;
 
         Ok(())
     }
@@ -114,69 +359,178 @@ impl<C: Client> NodeService<C> {
     /// This method is used to check if the predecessor is still alive. If not, the predecessor is
     /// set to `None`.
     ///
+    /// Any other failure of the ping keeps the predecessor and it's returned as an error.
+    ///
     /// > **Note**
     /// >
-    /// > This method should be called periodically.
-    pub fn check_predecessor(&mut self) {
+    /// > This method should be called periodically, see [`Maintenance`](crate::Maintenance).
+    pub async fn check_predecessor(&self) -> Result<(), error::ServiceError> {
         if let Some(predecessor) = self.store.predecessor() {
             let client: C = predecessor.client();
-            if let Err(ClientError::ConnectionFailed(_)) = client.ping() {
-                self.store.unset_predecessor();
-            };
+            match client.ping().await {
+                Ok(()) => {}
+                Err(ClientError::ConnectionFailed(_) | ClientError::Timeout(_)) => {
+                    self.store.unset_predecessor_if(&predecessor);
+                }
+                Err(err) => return Err(err.into()),
+            }
         }
+
+        Ok(())
     }
 
     /// Fix fingers
     ///
-    /// This method is used to fix the fingers. It iterates over all fingers and re-requests the
+    /// This method is used to fix the fingers. It iterates over the fingers and re-requests the
     /// successor of the finger's id. Then sets the successor of the finger to the retrieved node.
     ///
+    /// Depending on the configured [`FixFingersMode`], either the whole finger table is refreshed,
+    /// or only the next batch of fingers, continuing where the previous call stopped.
+    ///
+    /// A finger whose lookup fails keeps its node and doesn't keep the other fingers from being
+    /// refreshed, the first error is returned once all of them were tried.
+    ///
     /// > **Note**
     /// >
-    /// > This method should be called periodically.
-    pub fn fix_fingers(&mut self) {
-        for i in 0..self.store.finger_table.len() {
-            let finger_id = Finger::finger_id(self.id, (i + 1) as u8);
-            if let Ok(successor) =  self.find_successor(finger_id) {
-                self.store.finger_table[i].node = successor;
+    /// > This method should be called periodically, see [`Maintenance`](crate::Maintenance).
+    pub async fn fix_fingers(&self) -> Result<(), error::ServiceError> {
+        let indexes = match self.config.fix_fingers_mode {
+            FixFingersMode::Full => (0..self.store.finger_count()).collect(),
+            FixFingersMode::Incremental(batch) => self.store.next_fingers(batch),
+        };
+
+        let mut result = Ok(());
+        for i in indexes {
+            let finger_id = Finger::sized_finger_id(self.config.ring.bits, self.id, (i + 1) as u8);
+            match self.find_successor(finger_id).await {
+                Ok(successor) => self.store.set_finger(i, successor),
+                Err(err) => {
+                    log::debug!("Lookup of finger {} of node {} failed: {}", i, self.id, err);
+                    result = result.and(Err(err));
+                }
             }
         }
+
+        result
     }
 
-    fn closest_preceding_node(&self, id: u64) -> &Node {
-        for finger in self.store.finger_table.iter().rev() {
-            if finger.start > self.id && finger.node.id < id && finger.start < id {
-                return &finger.node;
-            } else if id < self.id {
-                // if the id is smaller than the current node, we return the last finger
-                return &finger.node;
+    /// Get the closest preceding node of the given id from the finger table.
+    ///
+    /// Fingers marked as suspect are skipped. If no finger precedes the id, the successor is
+    /// returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id to find the closest preceding node for
+    pub fn closest_preceding_node(&self, id: u64) -> Node {
+        self.preceding_nodes(id).into_iter().next()
+            .unwrap_or_else(|| self.store.successor())
+    }
+
+    /// Get the nodes a lookup of the given id can be forwarded to, the best one first.
+    ///
+    /// The fingers preceding the id come first, from the closest to the farthest one, followed by
+    /// the successor list. Fingers marked as suspect are skipped, and so is the node itself, since
+    /// a lookup forwarded to itself would never make progress.
+    fn preceding_nodes(&self, id: u64) -> Vec<Node> {
+        let mut nodes: Vec<Node> = Vec::new();
+        for finger in self.store.fingers().into_iter().rev().filter(|f| !f.suspect && f.node.id != self.id) {
+            // if the id is smaller than the current node, every finger is a candidate, starting
+            // from the last one
+            let precedes = (finger.start > self.id && finger.node.id < id && finger.start < id) || id < self.id;
+            if precedes && !nodes.contains(&finger.node) {
+                nodes.push(finger.node);
+            }
+        }
+
+        for successor in self.store.successor_list() {
+            if successor.id != self.id && !nodes.contains(&successor) {
+                nodes.push(successor);
             }
         }
 
-        self.store.successor()
+        nodes
     }
 }
 
 pub mod error {
+    use std::error::Error;
     use std::fmt::Display;
-    use crate::client;
+    use crate::client::ClientError;
+    use crate::{Node, StorageError};
 
+    /// Error returned by the node service
+    ///
+    /// Failures of the remote nodes are mapped to typed variants, so the callers can tell a dead
+    /// peer from a bug without parsing the message.
     #[derive(Debug)]
     pub enum ServiceError {
+        /// The node couldn't be reached
+        Unreachable(Node),
+        /// The node didn't respond in time
+        Timeout(Node),
+        /// The node refused to handle the request
+        Rejected(String),
+        /// The node speaks a different version of the protocol
+        ProtocolMismatch(String),
+        /// The lookup of the id didn't reach its owner within the given number of hops
+        HopLimitExceeded(u64, usize),
+        /// Fewer replicas than required acknowledged the request, with the number of the required
+        /// and the acknowledged replicas
+        QuorumNotReached(usize, usize),
+        /// An argument of the request is not valid
+        InvalidArgument(String),
+        /// Any other error of a client, available through [`source`](Error::source)
+        Client(ClientError),
+        /// The storage of the key-value store failed, available through [`source`](Error::source)
+        Storage(StorageError),
         Unexpected(String),
     }
 
-    impl From<client::ClientError> for ServiceError {
-        fn from(err: client::ClientError) -> Self {
-            Self::Unexpected(format!("Client error: {}", err))
+    impl From<ClientError> for ServiceError {
+        fn from(err: ClientError) -> Self {
+            match err {
+                ClientError::ConnectionFailed(node) => Self::Unreachable(node),
+                ClientError::Timeout(node) => Self::Timeout(node),
+                ClientError::Rejected(reason) => Self::Rejected(reason),
+                ClientError::ProtocolMismatch(message) => Self::ProtocolMismatch(message),
+                err => Self::Client(err),
+            }
+        }
+    }
+
+    impl From<StorageError> for ServiceError {
+        fn from(err: StorageError) -> Self {
+            Self::Storage(err)
         }
     }
 
     impl Display for ServiceError {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             match self {
+                Self::Unreachable(node) => write!(f, "Node {} is unreachable", node.addr()),
+                Self::Timeout(node) => write!(f, "Request to node {} timed out", node.addr()),
+                Self::Rejected(reason) => write!(f, "Request rejected: {}", reason),
+                Self::ProtocolMismatch(message) => write!(f, "Protocol mismatch: {}", message),
+                Self::HopLimitExceeded(id, max_hops) => write!(f, "Lookup of id {} exceeded {} hops", id, max_hops),
+                Self::QuorumNotReached(required, acknowledged) => {
+                    write!(f, "Only {} of {} required replicas acknowledged the request", acknowledged, required)
+                }
+                Self::InvalidArgument(message) => write!(f, "Invalid argument: {}", message),
+                Self::Client(err) => write!(f, "Client error: {}", err),
+                Self::Storage(err) => write!(f, "Storage error: {}", err),
                 Self::Unexpected(message) => write!(f, "{}", message),
             }
         }
     }
+
+    impl Error for ServiceError {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            match self {
+                Self::Client(err) => Some(err),
+                Self::Storage(err) => Some(err),
+                _ => None,
+            }
+        }
+    }
 }
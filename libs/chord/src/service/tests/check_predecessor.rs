@@ -1,15 +1,15 @@
 use std::net::SocketAddr;
 use crate::client::{ClientError, MockClient};
 use crate::service::tests;
-use crate::NodeService;
+use crate::{NodeService, ServiceError};
 use crate::service::tests::{get_lock, MTX};
 
-#[test]
-fn when_predecessor_is_up_it_should_not_be_removed() {
-    let _m = get_lock(&MTX);
+#[tokio::test]
+async fn when_predecessor_is_up_it_should_not_be_removed() {
+    let _m = get_lock(&MTX).await;
     let ctx = MockClient::init_context();
 
-    ctx.expect().returning(|addr: SocketAddr| {
+    ctx.expect().returning(|addr: SocketAddr, _| {
         let mut client = MockClient::new();
         if addr.port() == 42012 {
             client.expect_ping()
@@ -21,22 +21,22 @@ fn when_predecessor_is_up_it_should_not_be_removed() {
         client
     });
 
-    let mut service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
     service.store.set_successor(tests::node(16));
     service.store.set_predecessor(tests::node(12));
 
-    service.check_predecessor();
+    service.check_predecessor().await.unwrap();
 
     assert!(service.store.predecessor().is_some());
     assert_eq!(service.store.predecessor().unwrap().id, 12);
 }
 
-#[test]
-fn when_predecessor_is_down_it_should_be_removed() {
-    let _m = get_lock(&MTX);
+#[tokio::test]
+async fn when_predecessor_is_down_it_should_be_removed() {
+    let _m = get_lock(&MTX).await;
     let ctx = MockClient::init_context();
 
-    ctx.expect().returning(|addr: SocketAddr| {
+    ctx.expect().returning(|addr: SocketAddr, _| {
         let mut client = MockClient::new();
         if addr.port() == 42016 {
             client.expect_ping()
@@ -49,21 +49,21 @@ fn when_predecessor_is_down_it_should_be_removed() {
         client
     });
 
-    let mut service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
     service.store.set_successor(tests::node(16));
     service.store.set_predecessor(tests::node(16));
 
-    service.check_predecessor();
+    service.check_predecessor().await.unwrap();
 
     assert!(service.store.predecessor().is_none());
 }
 
-#[test]
-fn when_ping_fails_with_unexpected_error_predecessor_should_not_be_removed() {
-    let _m = get_lock(&MTX);
+#[tokio::test]
+async fn when_ping_fails_with_unexpected_error_predecessor_should_not_be_removed() {
+    let _m = get_lock(&MTX).await;
     let ctx = MockClient::init_context();
 
-    ctx.expect().returning(|addr: SocketAddr| {
+    ctx.expect().returning(|addr: SocketAddr, _| {
         let mut client = MockClient::new();
         if addr.port() == 42008 {
             client.expect_ping()
@@ -75,11 +75,13 @@ fn when_ping_fails_with_unexpected_error_predecessor_should_not_be_removed() {
         client
     });
 
-    let mut service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
     service.store.set_successor(tests::node(16));
     service.store.set_predecessor(tests::node(8));
 
-    service.check_predecessor();
+    let result = service.check_predecessor().await;
+
+    assert!(matches!(result, Err(ServiceError::Client(ClientError::Unexpected(_)))));
 
     assert!(service.store.predecessor().is_some());
     assert_eq!(service.store.predecessor().unwrap().id, 8);
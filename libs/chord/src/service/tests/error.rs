@@ -0,0 +1,48 @@
+use std::error::Error;
+use std::io;
+use crate::client::ClientError;
+use crate::{ServiceError, StorageError};
+use crate::service::tests;
+
+#[test]
+fn client_errors_should_be_mapped_to_typed_service_errors() {
+    let err = ServiceError::from(ClientError::ConnectionFailed(tests::node(16)));
+    assert!(matches!(err, ServiceError::Unreachable(node) if node.id == 16));
+
+    let err = ServiceError::from(ClientError::Timeout(tests::node(16)));
+    assert!(matches!(err, ServiceError::Timeout(node) if node.id == 16));
+
+    let err = ServiceError::from(ClientError::Rejected("Not joined".to_string()));
+    assert!(matches!(err, ServiceError::Rejected(reason) if reason == "Not joined"));
+
+    let err = ServiceError::from(ClientError::ProtocolMismatch("v2".to_string()));
+    assert!(matches!(err, ServiceError::ProtocolMismatch(message) if message == "v2"));
+}
+
+#[test]
+fn service_error_should_chain_client_error_source() {
+    let io_error = io::Error::new(io::ErrorKind::BrokenPipe, "Broken pipe");
+    let err = ServiceError::from(ClientError::Transport(Box::new(io_error)));
+
+    assert_eq!(err.to_string(), "Client error: Transport error: Broken pipe");
+    let client_error = err.source().unwrap();
+    assert_eq!(client_error.to_string(), "Transport error: Broken pipe");
+    let io_error = client_error.source().unwrap().downcast_ref::<io::Error>().unwrap();
+    assert_eq!(io_error.kind(), io::ErrorKind::BrokenPipe);
+}
+
+#[test]
+fn service_error_should_chain_storage_error_source() {
+    let io_error = io::Error::new(io::ErrorKind::StorageFull, "No space left");
+    let err = ServiceError::from(StorageError::from(io_error));
+
+    assert_eq!(err.to_string(), "Storage error: Storage I/O failed: No space left");
+    let io_error = err.source().unwrap().source().unwrap().downcast_ref::<io::Error>().unwrap();
+    assert_eq!(io_error.kind(), io::ErrorKind::StorageFull);
+}
+
+#[test]
+fn typed_service_errors_should_not_have_source() {
+    assert!(ServiceError::Unreachable(tests::node(16)).source().is_none());
+    assert!(ServiceError::HopLimitExceeded(16, 8).source().is_none());
+}
@@ -1,26 +1,26 @@
 use std::net::SocketAddr;
-use crate::client::MockClient;
-use crate::{NodeService};
+use crate::client::{ClientError, MockClient};
+use crate::{NodeService, ServiceError};
 use crate::service::tests;
 use crate::service::tests::{get_lock, MTX};
 
-#[test]
-fn test_find_successor() {
-    let _m = get_lock(&MTX);
+#[tokio::test]
+async fn test_find_successor() {
+    let _m = get_lock(&MTX).await;
     let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
-    let result = service.find_successor(10);
+    let result = service.find_successor(10).await;
     assert!(result.is_ok());
     let successor = result.unwrap();
 
     assert_eq!(successor.id, 8);
 }
 
-#[test]
-fn find_successor_with_2_nodes() {
-    let _m = get_lock(&MTX);
+#[tokio::test]
+async fn find_successor_with_2_nodes() {
+    let _m = get_lock(&MTX).await;
     let ctx = MockClient::init_context();
 
-    ctx.expect().returning(|_| {
+    ctx.expect().returning(|_, _| {
         let mut client = MockClient::new();
         client.expect_find_successor()
             .times(1)
@@ -30,19 +30,19 @@ fn find_successor_with_2_nodes() {
         client
     });
 
-    let mut service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
     service.store.set_successor(tests::node(16));
 
-    assert_eq!(service.find_successor(10).unwrap().id, 16);
-    assert_eq!(service.find_successor(2).unwrap().id, 6);
+    assert_eq!(service.find_successor(10).await.unwrap().id, 16);
+    assert_eq!(service.find_successor(2).await.unwrap().id, 6);
 }
 
-#[test]
-fn find_successor_using_finger_table_nodes() {
-    let _m = get_lock(&MTX);
+#[tokio::test]
+async fn find_successor_using_finger_table_nodes() {
+    let _m = get_lock(&MTX).await;
     let ctx = MockClient::init_context();
 
-    ctx.expect().returning(|addr: SocketAddr| {
+    ctx.expect().returning(|addr: SocketAddr, _| {
         let mut client = MockClient::new();
         if addr.port() == 42035 {
             client.expect_find_successor()
@@ -62,16 +62,16 @@ fn find_successor_using_finger_table_nodes() {
         client
     });
 
-    let mut service: NodeService<MockClient> = NodeService::default();
+    let service: NodeService<MockClient> = NodeService::default();
     service.with_fingers(vec![1, 10, 35, 129]);
 
-    assert_eq!(service.find_successor(40).unwrap().id, 111);
-    assert_eq!(service.find_successor(2).unwrap().id, 5);
+    assert_eq!(service.find_successor(40).await.unwrap().id, 111);
+    assert_eq!(service.find_successor(2).await.unwrap().id, 5);
 }
 
 #[test]
 fn check_closest_preceding_node() {
-    let mut service: NodeService<MockClient> = NodeService::default();
+    let service: NodeService<MockClient> = NodeService::default();
     service.with_fingers(vec![1, 10, 35, 129]);
 
     assert_eq!(service.closest_preceding_node(2).id, 1);
@@ -80,3 +80,139 @@ fn check_closest_preceding_node() {
     assert_eq!(service.closest_preceding_node(100).id, 35);
     assert_eq!(service.closest_preceding_node(150).id, 1);
 }
+
+#[tokio::test]
+async fn find_successor_should_fall_back_to_next_finger_when_finger_is_dead() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let mut client = MockClient::new();
+        if addr.port() == 42035 {
+            client.expect_find_successor()
+                .times(1)
+                .returning(|_| {
+                    Err(ClientError::ConnectionFailed(tests::node(35)))
+                });
+        }
+
+        if addr.port() == 42010 {
+            client.expect_find_successor()
+                .times(1)
+                .returning(|_| {
+                    Ok(tests::node(111))
+                });
+        }
+        client
+    });
+
+    let service: NodeService<MockClient> = NodeService::default();
+    service.with_fingers(vec![1, 10, 35, 129]);
+
+    assert_eq!(service.find_successor(40).await.unwrap().id, 111);
+    assert!(service.store.fingers().iter().filter(|f| f.node.id == 35).all(|f| f.suspect));
+
+    // The suspect finger is avoided by the following lookups
+    assert_eq!(service.closest_preceding_node(40).id, 10);
+    assert_eq!(service.find_successor(40).await.unwrap().id, 111);
+}
+
+#[tokio::test]
+async fn find_successor_should_fall_back_to_successor_list_when_fingers_are_dead() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let mut client = MockClient::new();
+        if addr.port() == 42021 {
+            client.expect_find_successor()
+                .returning(|_| {
+                    Ok(tests::node(42))
+                });
+        } else {
+            client.expect_find_successor()
+                .returning(move |_| {
+                    Err(ClientError::ConnectionFailed(tests::node(addr.port() as u64 - 42000)))
+                });
+        }
+        client
+    });
+
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42008)));
+    service.store.set_successor(tests::node(16));
+    service.store.update_successor_list(vec![tests::node(21), tests::node(32)]);
+    service.store.set_finger(4, tests::node(32));
+
+    assert_eq!(service.find_successor(40).await.unwrap().id, 42);
+}
+
+#[tokio::test]
+async fn find_successor_should_fail_when_no_node_is_reachable() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let mut client = MockClient::new();
+        client.expect_find_successor()
+            .returning(move |_| {
+                Err(ClientError::ConnectionFailed(tests::node(addr.port() as u64 - 42000)))
+            });
+        client
+    });
+
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42008)));
+    service.store.set_successor(tests::node(16));
+    service.store.update_successor_list(vec![tests::node(21)]);
+
+    let result = service.find_successor(40).await;
+    assert!(matches!(result, Err(ServiceError::Unreachable(_))));
+}
+
+#[tokio::test]
+async fn find_successor_should_not_fall_back_on_unexpected_error() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|_, _| {
+        let mut client = MockClient::new();
+        client.expect_find_successor()
+            .times(1)
+            .returning(|_| {
+                Err(ClientError::Unexpected("Test".to_string()))
+            });
+        client
+    });
+
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42008)));
+    service.store.set_successor(tests::node(16));
+    service.store.update_successor_list(vec![tests::node(21)]);
+
+    assert!(service.find_successor(40).await.is_err());
+    assert!(service.store.fingers().iter().all(|f| !f.suspect));
+}
+
+#[tokio::test]
+async fn find_successor_should_not_forward_lookup_to_itself() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let mut client = MockClient::new();
+        if addr.port() == 42001 {
+            client.expect_find_successor()
+                .never();
+        } else {
+            client.expect_find_successor()
+                .returning(|_| {
+                    Ok(tests::node(40))
+                });
+        }
+        client
+    });
+
+    // The fingers other than the successor still point to the node itself
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
+    service.store.set_successor(tests::node(16));
+
+    assert_eq!(service.find_successor(2).await.unwrap().id, 40);
+}
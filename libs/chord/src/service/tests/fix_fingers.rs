@@ -1,14 +1,16 @@
 use std::net::SocketAddr;
-use crate::client::MockClient;
-use crate::NodeService;
+use mockall::predicate;
+use crate::client::{ClientError, MockClient};
+use crate::{Config, FixFingersMode, NodeService, RingConfig, ServiceError};
+use crate::service::tests;
 use crate::service::tests::{get_lock, MTX};
 
-#[test]
-fn fix_fingers_test() {
-    let _m = get_lock(&MTX);
+#[tokio::test]
+async fn fix_fingers_test() {
+    let _m = get_lock(&MTX).await;
     let ctx = MockClient::init_context();
 
-    ctx.expect().returning(|addr: SocketAddr| {
+    ctx.expect().returning(|addr: SocketAddr, _| {
         let mut client = MockClient::new();
         if addr.port() == 42014 { client.mock_find_successor(16, 19); }
         if addr.port() == 42019 { client.mock_find_successor(24, 28); }
@@ -16,16 +18,98 @@ fn fix_fingers_test() {
 
         client
     });
-    let mut service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42008)));
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42008)));
     service.with_fingers_sized(6, vec![1, 14, 21, 32, 38, 42, 48, 51]);
 
-    assert_eq!(service.store.finger_table.len(), 6);
+    assert_eq!(service.store.finger_count(), 6);
     assert_eq!(service.collect_finger_node_ids(), vec![14, 14, 14, 21, 32, 42]);
     assert_eq!(service.collect_finger_ids(), vec![9, 10, 12, 16, 24, 40]);
 
-    service.fix_fingers();
+    service.fix_fingers().await.unwrap();
 
-    assert_eq!(service.store.finger_table.len(), 6);
+    assert_eq!(service.store.finger_count(), 6);
     assert_eq!(service.collect_finger_node_ids(), vec![14, 14, 14, 19, 28, 42]);
     assert_eq!(service.collect_finger_ids(), vec![9, 10, 12, 16, 24, 40]);
 }
+
+#[tokio::test]
+async fn when_lookup_fails_fix_fingers_should_fix_other_fingers_and_return_error() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let mut client = MockClient::new();
+        if addr.port() == 42014 { client.mock_find_successor(16, 19); }
+        if addr.port() == 42032 { client.mock_find_successor(40, 42); }
+        client.expect_find_successor()
+            .with(predicate::eq(24))
+            .returning(|_| Err(ClientError::Unexpected("Error".to_string())));
+
+        client
+    });
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42008)));
+    service.with_fingers_sized(6, vec![1, 14, 21, 32, 38, 42, 48, 51]);
+
+    let result = service.fix_fingers().await;
+
+    assert!(matches!(result, Err(ServiceError::Client(ClientError::Unexpected(_)))));
+    assert_eq!(service.collect_finger_node_ids(), vec![14, 14, 14, 19, 32, 42]);
+}
+
+#[tokio::test]
+async fn incremental_fix_fingers_test() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let mut client = MockClient::new();
+        if addr.port() == 42014 { client.mock_find_successor(16, 19); }
+        if addr.port() == 42019 { client.mock_find_successor(24, 28); }
+        if addr.port() == 42028 { client.mock_find_successor(40, 42); }
+
+        client
+    });
+    let config = Config { fix_fingers_mode: FixFingersMode::Incremental(2), ..Config::default() };
+    let service: NodeService<MockClient> = NodeService::with_id_and_config(8, SocketAddr::from(([127, 0, 0, 1], 42008)), config);
+    service.with_fingers_sized(6, vec![1, 14, 21, 32, 38, 42, 48, 51]);
+
+    assert_eq!(service.collect_finger_node_ids(), vec![14, 14, 14, 21, 32, 42]);
+
+    service.fix_fingers().await.unwrap();
+    assert_eq!(service.collect_finger_node_ids(), vec![14, 14, 14, 21, 32, 42]);
+
+    service.fix_fingers().await.unwrap();
+    assert_eq!(service.collect_finger_node_ids(), vec![14, 14, 14, 19, 32, 42]);
+
+    service.fix_fingers().await.unwrap();
+    assert_eq!(service.collect_finger_node_ids(), vec![14, 14, 14, 19, 28, 42]);
+}
+
+#[tokio::test]
+async fn fix_fingers_on_small_ring_should_wrap_finger_ids() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let mut client = MockClient::new();
+        if addr.port() == 42042 {
+            client.expect_find_successor()
+                .with(predicate::in_iter(vec![44, 48]))
+                .returning(|_| Ok(tests::node(48)));
+        }
+        if addr.port() == 42048 { client.mock_find_successor(56, 1); }
+        if addr.port() == 42014 { client.mock_find_successor(8, 14); }
+
+        client
+    });
+    let config = Config { ring: RingConfig::new(6), ..Config::default() };
+    let service: NodeService<MockClient> = NodeService::with_id_and_config(40, SocketAddr::from(([127, 0, 0, 1], 42040)), config);
+    assert_eq!(service.store.finger_count(), 6);
+
+    service.with_fingers_sized(6, vec![1, 14, 21, 32, 38, 42, 48, 51]);
+    assert_eq!(service.collect_finger_ids(), vec![41, 42, 44, 48, 56, 8]);
+
+    service.fix_fingers().await.unwrap();
+
+    assert_eq!(service.collect_finger_node_ids(), vec![42, 42, 48, 48, 1, 14]);
+}
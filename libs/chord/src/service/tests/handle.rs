@@ -0,0 +1,31 @@
+use crate::client::MockClient;
+use crate::{NodeHandle, NodeService};
+use crate::service::tests;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn handle_should_serve_requests_while_maintenance_is_running() {
+    let handle = NodeHandle::new(NodeService::<MockClient>::default());
+
+    let maintenance = handle.clone();
+    let fix_fingers = tokio::spawn(async move {
+        for _ in 0..10 {
+            maintenance.fix_fingers().await.unwrap();
+        }
+    });
+
+    let mut requests = Vec::new();
+    for id in 1..8 {
+        let handle = handle.clone();
+        requests.push(tokio::spawn(async move {
+            handle.notify(tests::node(id));
+            handle.find_successor(id).await.unwrap()
+        }));
+    }
+
+    for request in requests {
+        assert_eq!(request.await.unwrap().id, 8);
+    }
+    fix_fingers.await.unwrap();
+
+    assert_eq!(handle.store.predecessor().unwrap().id, 7);
+}
@@ -5,12 +5,12 @@ use crate::NodeService;
 use crate::service::tests;
 use crate::service::tests::{get_lock, MTX};
 
-#[test]
-fn join_test() {
-    let _m = get_lock(&MTX);
+#[tokio::test]
+async fn join_test() {
+    let _m = get_lock(&MTX).await;
     let ctx = MockClient::init_context();
 
-    ctx.expect().returning(|addr: SocketAddr| {
+    ctx.expect().returning(|addr: SocketAddr, _| {
         let mut client = MockClient::new();
         if addr.port() == 42115 {
             client.expect_find_successor()
@@ -23,19 +23,19 @@ fn join_test() {
 
         client
     });
-    let mut service: NodeService<MockClient> = NodeService::with_id(1, SocketAddr::from(([127, 0, 0, 1], 42001)));
+    let service: NodeService<MockClient> = NodeService::with_id(1, SocketAddr::from(([127, 0, 0, 1], 42001)));
 
-    service.join(tests::node(115)).unwrap();
+    service.join(tests::node(115)).await.unwrap();
 
     assert_eq!(service.store.successor().id, 115);
 }
 
-#[test]
-fn join_error_test() {
-    let _m = get_lock(&MTX);
+#[tokio::test]
+async fn join_error_test() {
+    let _m = get_lock(&MTX).await;
     let ctx = MockClient::init_context();
 
-    ctx.expect().returning(|addr: SocketAddr| {
+    ctx.expect().returning(|addr: SocketAddr, _| {
         let mut client = MockClient::new();
         if addr.port() == 42116 {
             client.expect_find_successor()
@@ -47,9 +47,9 @@ fn join_error_test() {
         }
         client
     });
-    let mut service: NodeService<MockClient> = NodeService::with_id(2, SocketAddr::from(([127, 0, 0, 1], 42001)));
+    let service: NodeService<MockClient> = NodeService::with_id(2, SocketAddr::from(([127, 0, 0, 1], 42001)));
 
-    let result = service.join(tests::node(116));
+    let result = service.join(tests::node(116)).await;
 
     assert!(result.is_err());
     let message = result.unwrap_err().to_string();
@@ -0,0 +1,113 @@
+use std::net::SocketAddr;
+use mockall::predicate;
+use crate::client::{ClientError, MockClient};
+use crate::service::tests;
+use crate::{Node, NodeService};
+use crate::service::tests::{get_lock, MTX};
+
+#[tokio::test]
+async fn leave_should_notify_predecessor_and_successor() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let mut client = MockClient::new();
+        if addr.port() == 42004 {
+            client.expect_successor_leaving()
+                .with(predicate::function(|n: &Node| n.id == 8), predicate::function(|n: &Node| n.id == 16))
+                .times(1)
+                .returning(|_, _| {
+                    Ok(())
+                });
+        }
+
+        if addr.port() == 42016 {
+            client.expect_predecessor_leaving()
+                .with(predicate::function(|n: &Node| n.id == 8), predicate::eq(Some(tests::node(4))))
+                .times(1)
+                .returning(|_, _| {
+                    Ok(())
+                });
+        }
+        client
+    });
+
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42008)));
+    service.store.set_successor(tests::node(16));
+    service.store.set_predecessor(tests::node(4));
+
+    assert!(service.leave().await.is_ok());
+}
+
+#[tokio::test]
+async fn leave_without_predecessor_should_only_notify_successor() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let mut client = MockClient::new();
+        if addr.port() == 42016 {
+            client.expect_predecessor_leaving()
+                .with(predicate::function(|n: &Node| n.id == 8), predicate::eq(None))
+                .times(1)
+                .returning(|_, _| {
+                    Ok(())
+                });
+        }
+        client
+    });
+
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42008)));
+    service.store.set_successor(tests::node(16));
+
+    assert!(service.leave().await.is_ok());
+}
+
+#[tokio::test]
+async fn leave_should_fail_when_successor_is_unreachable() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|_, _| {
+        let mut client = MockClient::new();
+        client.expect_predecessor_leaving()
+            .returning(|_, _| {
+                Err(ClientError::ConnectionFailed(tests::node(16)))
+            });
+        client
+    });
+
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42008)));
+    service.store.set_successor(tests::node(16));
+
+    assert!(service.leave().await.is_err());
+}
+
+#[test]
+fn when_successor_is_leaving_then_its_successor_should_take_over() {
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42008)));
+    service.with_fingers(vec![16, 32, 64]);
+    service.store.update_successor_list(vec![tests::node(32), tests::node(64)]);
+
+    service.successor_leaving(tests::node(16), tests::node(32));
+
+    assert_eq!(service.store.successor().id, 32);
+    let ids: Vec<u64> = service.successor_list().iter().map(|n| n.id).collect();
+    assert_eq!(ids, vec![32, 64]);
+    assert!(service.collect_finger_node_ids().iter().all(|id| *id != 16));
+}
+
+#[test]
+fn when_predecessor_is_leaving_then_its_predecessor_should_be_set() {
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42008)));
+    service.store.set_predecessor(tests::node(4));
+
+    service.predecessor_leaving(tests::node(2), Some(tests::node(1)));
+    assert_eq!(service.store.predecessor().unwrap().id, 4);
+
+    service.predecessor_leaving(tests::node(4), Some(tests::node(2)));
+    assert_eq!(service.store.predecessor().unwrap().id, 2);
+
+    service.predecessor_leaving(tests::node(2), Some(tests::node(8)));
+    assert!(service.store.predecessor().is_none());
+}
@@ -0,0 +1,222 @@
+use std::net::SocketAddr;
+use std::thread;
+use std::time::Duration;
+use mockall::predicate;
+use crate::client::MockClient;
+use crate::{LookupMode, NodeService, ServiceError};
+use crate::service::tests;
+use crate::service::tests::{get_lock, MTX};
+
+#[tokio::test]
+async fn iterative_lookup_should_be_resolved_locally_when_id_belongs_to_successor() {
+    let _m = get_lock(&MTX).await;
+    let service: NodeService<MockClient> = NodeService::default();
+    service.with_fingers(vec![1, 10, 35, 129]);
+
+    assert_eq!(service.lookup(9, LookupMode::iterative()).await.unwrap().id, 10);
+}
+
+#[tokio::test]
+async fn iterative_lookup_should_ask_each_hop_for_next_one() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let mut client = MockClient::new();
+        if addr.port() == 42001 {
+            client.expect_successor()
+                .returning(|| {
+                    Ok(tests::node(10))
+                });
+            client.expect_closest_preceding_node()
+                .with(predicate::eq(200))
+                .returning(|_| {
+                    Ok(tests::node(129))
+                });
+        }
+
+        if addr.port() == 42129 {
+            client.expect_successor()
+                .returning(|| {
+                    Ok(tests::node(1))
+                });
+        }
+        client
+    });
+
+    let service: NodeService<MockClient> = NodeService::default();
+    service.with_fingers(vec![1, 10, 35, 129]);
+
+    assert_eq!(service.lookup(200, LookupMode::iterative()).await.unwrap().id, 1);
+}
+
+#[tokio::test]
+async fn iterative_lookup_should_fail_when_max_hops_is_exceeded() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let mut client = MockClient::new();
+        if addr.port() == 42001 {
+            client.expect_successor()
+                .returning(|| {
+                    Ok(tests::node(10))
+                });
+            client.expect_closest_preceding_node()
+                .returning(|_| {
+                    Ok(tests::node(129))
+                });
+        }
+        client
+    });
+
+    let service: NodeService<MockClient> = NodeService::default();
+    service.with_fingers(vec![1, 10, 35, 129]);
+
+    let mode = LookupMode::Iterative { max_hops: 1, hop_timeout: Duration::from_secs(1) };
+    let result = service.lookup(200, mode).await;
+
+    assert!(matches!(result, Err(ServiceError::HopLimitExceeded(200, 1))));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn iterative_lookup_should_fail_when_hop_times_out() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|_, _| {
+        let mut client = MockClient::new();
+        client.expect_successor()
+            .returning(|| {
+                thread::sleep(Duration::from_millis(200));
+                Ok(tests::node(10))
+            });
+        client
+    });
+
+    let service: NodeService<MockClient> = NodeService::default();
+    service.with_fingers(vec![1, 10, 35, 129]);
+
+    let mode = LookupMode::Iterative { max_hops: 8, hop_timeout: Duration::from_millis(10) };
+    let result = service.lookup(200, mode).await;
+
+    match result {
+        Err(ServiceError::Timeout(node)) => assert_eq!(node.addr().port(), 42001),
+        _ => panic!("Expected a timeout"),
+    }
+}
+
+#[tokio::test]
+async fn iterative_lookup_trace_should_contain_every_hop() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let mut client = MockClient::new();
+        if addr.port() == 42001 {
+            client.expect_successor()
+                .returning(|| {
+                    Ok(tests::node(10))
+                });
+            client.expect_closest_preceding_node()
+                .with(predicate::eq(200))
+                .returning(|_| {
+                    Ok(tests::node(129))
+                });
+        }
+
+        if addr.port() == 42129 {
+            client.expect_successor()
+                .returning(|| {
+                    Ok(tests::node(1))
+                });
+        }
+        client
+    });
+
+    let service: NodeService<MockClient> = NodeService::default();
+    service.with_fingers(vec![1, 10, 35, 129]);
+
+    let trace = service.lookup_with_trace(200, LookupMode::iterative()).await.unwrap();
+
+    assert_eq!(trace.owner.id, 1);
+    let ids: Vec<u64> = trace.hops.iter().map(|hop| hop.id).collect();
+    assert_eq!(ids, vec![8, 1, 129]);
+    assert_eq!(trace.hops[2].addr, tests::node(129).addr());
+}
+
+#[tokio::test]
+async fn only_iterative_lookup_trace_should_contain_every_hop_of_long_path() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let mut client = MockClient::new();
+        let (successor, next) = match addr.port() {
+            42001 => (10, 129),
+            42129 => (150, 190),
+            _ => (210, 210),
+        };
+        client.expect_successor()
+            .returning(move || {
+                Ok(tests::node(successor))
+            });
+        client.expect_closest_preceding_node()
+            .with(predicate::eq(200))
+            .returning(move |_| {
+                Ok(tests::node(next))
+            });
+        // The forwarded lookup takes the same path, hidden from the originator
+        client.expect_find_successor()
+            .with(predicate::eq(200))
+            .returning(|_| {
+                Ok(tests::node(210))
+            });
+        client
+    });
+
+    let service: NodeService<MockClient> = NodeService::default();
+    service.with_fingers(vec![1, 10, 35, 129]);
+
+    let trace = service.lookup_with_trace(200, LookupMode::iterative()).await.unwrap();
+    assert_eq!(trace.owner.id, 210);
+    let ids: Vec<u64> = trace.hops.iter().map(|hop| hop.id).collect();
+    assert_eq!(ids, vec![8, 1, 129, 190]);
+
+    let trace = service.lookup_with_trace(200, LookupMode::Recursive).await.unwrap();
+    assert_eq!(trace.owner.id, 210);
+    let ids: Vec<u64> = trace.hops.iter().map(|hop| hop.id).collect();
+    assert_eq!(ids, vec![8, 1]);
+}
+
+#[tokio::test]
+async fn recursive_lookup_trace_should_contain_first_forwarded_hop() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let mut client = MockClient::new();
+        if addr.port() == 42035 {
+            client.expect_find_successor()
+                .with(predicate::eq(40))
+                .times(1)
+                .returning(|_| {
+                    Ok(tests::node(111))
+                });
+        }
+        client
+    });
+
+    let service: NodeService<MockClient> = NodeService::default();
+    service.with_fingers(vec![1, 10, 35, 129]);
+
+    let trace = service.lookup_with_trace(40, LookupMode::Recursive).await.unwrap();
+
+    assert_eq!(trace.owner.id, 111);
+    let ids: Vec<u64> = trace.hops.iter().map(|hop| hop.id).collect();
+    assert_eq!(ids, vec![8, 35]);
+
+    let trace = service.lookup_with_trace(9, LookupMode::Recursive).await.unwrap();
+    assert_eq!(trace.owner.id, 10);
+    assert_eq!(trace.hops.len(), 1);
+}
@@ -0,0 +1,80 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use crate::client::{ClientError, MockClient};
+use crate::{Maintenance, MaintenanceConfig, NodeService, Routine};
+use crate::service::tests::{get_lock, MTX};
+
+fn config() -> MaintenanceConfig {
+    MaintenanceConfig {
+        stabilize_interval: Duration::from_secs(1),
+        fix_fingers_interval: Duration::from_secs(1),
+        check_predecessor_interval: Duration::from_secs(1),
+        sync_replicas_interval: Duration::from_secs(1),
+        anti_entropy_interval: Duration::from_secs(1),
+        replay_hints_interval: Duration::from_secs(1),
+        collect_tombstones_interval: Duration::from_secs(1),
+        snapshot_interval: Duration::from_secs(1),
+        jitter: Duration::from_millis(100),
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn maintenance_should_report_routine_errors() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|_, _| {
+        let mut client = MockClient::new();
+        client.expect_predecessor()
+            .returning(|| {
+                Ok(None)
+            });
+        client.expect_successor_list()
+            .returning(|| {
+                Ok(vec![])
+            });
+        client.expect_notify()
+            .returning(|_| {
+                Err(ClientError::Unexpected("Test".to_string()))
+            });
+        client
+    });
+
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let reported = errors.clone();
+    let mut maintenance = Maintenance::new(NodeService::<MockClient>::default(), config())
+        .on_error(move |routine, err| {
+            reported.lock().unwrap().push((routine, err.to_string()));
+        });
+
+    maintenance.start();
+    assert!(maintenance.is_running());
+
+    tokio::time::sleep(Duration::from_millis(3500)).await;
+    maintenance.stop();
+    assert!(!maintenance.is_running());
+
+    let errors = errors.lock().unwrap().clone();
+    assert_eq!(errors.len(), 3);
+    assert!(errors.iter().all(|(routine, err)| *routine == Routine::Stabilize && err == "Client error: Test"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn stopped_maintenance_should_not_run_routines() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|_, _| {
+        let mut client = MockClient::new();
+        client.expect_predecessor()
+            .never();
+        client
+    });
+
+    let mut maintenance = Maintenance::new(NodeService::<MockClient>::default(), config());
+    maintenance.start();
+    maintenance.stop();
+
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    assert!(!maintenance.is_running());
+}
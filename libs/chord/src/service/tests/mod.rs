@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 use std::net::SocketAddr;
-use crate::{Node, NodeService};
+use crate::{Config, Node, NodeService, RingConfig, Sha1Hasher};
 use crate::client::MockClient;
 
 mod find_successor;
@@ -9,30 +9,33 @@ mod notify;
 mod stabilize;
 mod check_predecessor;
 mod fix_fingers;
+mod successor_list;
+mod leave;
+mod lookup;
+mod handle;
+mod maintenance;
+mod virtual_host;
+mod error;
+mod snapshot;
 
 use lazy_static::lazy_static;
-use std::sync::{Mutex, MutexGuard};
+use tokio::sync::{Mutex, MutexGuard};
 use mockall::predicate;
 use crate::node::Finger;
 use crate::node::store::NodeStore;
 
 lazy_static! {
-    static ref MTX: Mutex<()> = Mutex::new(());
+    pub(crate) static ref MTX: Mutex<()> = Mutex::new(());
 }
 
-// When a test panics, it will poison the Mutex. Since we don't actually
-// care about the state of the data we ignore that it is poisoned and grab
-// the lock regardless.  If you just do `let _m = &MTX.lock().unwrap()`, one
-// test panicking will cause all other tests that try and acquire a lock on
-// that Mutex to also panic.
-fn get_lock(m: &'static Mutex<()>) -> MutexGuard<'static, ()> {
-    match m.lock() {
-        Ok(guard) => guard,
-        Err(poisoned) => poisoned.into_inner(),
-    }
+// The mocked `Client::init` expectations are global, so the tests which set them up
+// have to run one at a time. The lock is held across `.await` points, so it has to be
+// an async aware Mutex. Unlike `std::sync::Mutex` it's not poisoned when a test panics.
+pub(crate) async fn get_lock(m: &'static Mutex<()>) -> MutexGuard<'static, ()> {
+    m.lock().await
 }
 
-fn node(id: u64) -> Node {
+pub(crate) fn node(id: u64) -> Node {
     let addr = SocketAddr::from(([127, 0, 0, 1], 42000 + id as u16));
     Node::with_id(id, addr)
 }
@@ -40,20 +43,21 @@ fn node(id: u64) -> Node {
 impl Default for NodeService<MockClient> {
     fn default() -> Self {
         let node = Node::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
-        let store = NodeStore::new(node.clone());
+        let store = NodeStore::new(node.clone(), &Config::default());
         Self {
             id: node.id,
             addr: node.addr,
             store,
+            config: Config::default(),
             phantom: PhantomData
         }
     }
 }
 
 impl NodeService<MockClient> {
-    fn find_closest_successor(id: u64, nodes: &Vec<Node>) -> Node {
-        let mut nodes = nodes.clone();
-        nodes.sort_by(|b, a| a.id.cmp(&b.id));
+    fn find_closest_successor(id: u64, nodes: &[Node]) -> Node {
+        let mut nodes = nodes.to_vec();
+        nodes.sort_by_key(|n| std::cmp::Reverse(n.id));
 
         let smallest = nodes.last().unwrap().clone();
         let mut closest = nodes[0].clone();
@@ -61,9 +65,8 @@ impl NodeService<MockClient> {
             if node.id == id {
                 return node;
             }
-            if node.id < closest.id && node.id > id {
-                closest = node;
-            } else if node.id < id && Node::is_between_on_ring(id, closest.id, node.id) {
+            if (node.id < closest.id && node.id > id)
+                || (node.id < id && Node::is_between_on_ring(id, closest.id, node.id)) {
                 closest = node;
             }
         }
@@ -75,32 +78,36 @@ impl NodeService<MockClient> {
         }
     }
 
-    pub(crate) fn with_fingers(&mut self, nodes_ids: Vec<u64>) {
+    pub(crate) fn store(&self) -> &NodeStore {
+        &self.store
+    }
+
+    pub(crate) fn with_fingers(&self, nodes_ids: Vec<u64>) {
         self.with_fingers_sized(64, nodes_ids);
     }
 
-    pub(crate) fn with_fingers_sized(&mut self, size: u8, nodes_ids: Vec<u64>) {
-        let mut nodes: Vec<Node> = nodes_ids.into_iter().map(|id| node(id)).collect();
-        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+    pub(crate) fn with_fingers_sized(&self, size: u8, nodes_ids: Vec<u64>) {
+        let mut nodes: Vec<Node> = nodes_ids.into_iter().map(node).collect();
+        nodes.sort_by_key(|n| n.id);
 
         let mut fingers = Vec::with_capacity(64);
 
         for i in 1..size+1 {
-            let finger_id = Finger::sized_finger_id(size, self.id, (i) as u8);
+            let finger_id = Finger::sized_finger_id(size, self.id, i);
 
             let closest = Self::find_closest_successor(finger_id, &nodes);
-            fingers.push(Finger { start: finger_id, node: closest });
+            fingers.push(Finger::new(finger_id, closest));
         }
 
-        self.store.finger_table = fingers;
+        self.store.set_fingers(fingers);
     }
 
     pub(crate) fn collect_finger_ids(&self) -> Vec<u64> {
-        self.store.finger_table.iter().map(|f| f.start).collect()
+        self.store.fingers().iter().map(|f| f.start).collect()
     }
 
     pub(crate) fn collect_finger_node_ids(&self) -> Vec<u64> {
-        self.store.finger_table.iter().map(|f| f.node.id).collect()
+        self.store.fingers().iter().map(|f| f.node.id).collect()
     }
 }
 
@@ -119,10 +126,10 @@ impl MockClient {
     /// use crate::client::MockClient;
     /// use crate::service::tests::{get_lock, MTX};
     ///
-    /// let _m = get_lock(&MTX);
+    /// let _m = get_lock(&MTX).await;
     /// let ctx = MockClient::init_context();
     ///
-    /// ctx.expect().returning(|addr: SocketAddr| {
+    /// ctx.expect().returning(|addr: SocketAddr, _| {
     ///     let mut client = MockClient::new();
     ///     // Node with port 42014 will respond with 21 as a successor for id 16.
     ///     if addr.port() == 42014 { client.mock_find_successor(16, 21); }
@@ -140,6 +147,7 @@ impl MockClient {
     }
 }
 
+#[allow(clippy::module_inception)]
 mod tests {
     use super::*;
 
@@ -149,54 +157,69 @@ mod tests {
         let nodes = vec![1, 16, 32, 64];
         service.with_fingers(nodes.clone());
 
-        assert_eq!(9, service.store.finger_table[0].start);
-        assert_eq!(16, service.store.finger_table[0].node.id);
-        assert_eq!(10, service.store.finger_table[1].start);
-        assert_eq!(16, service.store.finger_table[1].node.id);
-        assert_eq!(12, service.store.finger_table[2].start);
-        assert_eq!(16, service.store.finger_table[2].node.id);
-        assert_eq!(16, service.store.finger_table[3].start);
-        assert_eq!(16, service.store.finger_table[3].node.id);
+        assert_eq!(9, service.store.fingers()[0].start);
+        assert_eq!(16, service.store.fingers()[0].node.id);
+        assert_eq!(10, service.store.fingers()[1].start);
+        assert_eq!(16, service.store.fingers()[1].node.id);
+        assert_eq!(12, service.store.fingers()[2].start);
+        assert_eq!(16, service.store.fingers()[2].node.id);
+        assert_eq!(16, service.store.fingers()[3].start);
+        assert_eq!(16, service.store.fingers()[3].node.id);
 
-        assert_eq!(264, service.store.finger_table[8].start);
-        assert_eq!(1, service.store.finger_table[8].node.id);
+        assert_eq!(264, service.store.fingers()[8].start);
+        assert_eq!(1, service.store.fingers()[8].node.id);
 
         service.id = 2;
         service.with_fingers(nodes.clone());
 
-        assert_eq!(16, service.store.finger_table[0].node.id);
-        assert_eq!(16, service.store.finger_table[3].node.id);
-        assert_eq!(32, service.store.finger_table[4].node.id);
-        assert_eq!(64, service.store.finger_table[5].node.id);
-        assert_eq!(1, service.store.finger_table[6].node.id);
-        assert_eq!(1, service.store.finger_table[63].node.id);
+        assert_eq!(16, service.store.fingers()[0].node.id);
+        assert_eq!(16, service.store.fingers()[3].node.id);
+        assert_eq!(32, service.store.fingers()[4].node.id);
+        assert_eq!(64, service.store.fingers()[5].node.id);
+        assert_eq!(1, service.store.fingers()[6].node.id);
+        assert_eq!(1, service.store.fingers()[63].node.id);
 
         service.id = 154;
         service.with_fingers(nodes.clone());
 
-        assert_eq!(1, service.store.finger_table[0].node.id);
-        assert_eq!(1, service.store.finger_table[63].node.id);
+        assert_eq!(1, service.store.fingers()[0].node.id);
+        assert_eq!(1, service.store.fingers()[63].node.id);
 
         service.id = u64::MAX - 1;
         service.with_fingers(nodes.clone());
 
-        assert_eq!(1, service.store.finger_table[0].node.id);
-        assert_eq!(1, service.store.finger_table[1].node.id);
-        assert_eq!(2, service.store.finger_table[2].start);
-        assert_eq!(16, service.store.finger_table[2].node.id);
-        assert_eq!(14, service.store.finger_table[4].start);
-        assert_eq!(16, service.store.finger_table[4].node.id);
+        assert_eq!(1, service.store.fingers()[0].node.id);
+        assert_eq!(1, service.store.fingers()[1].node.id);
+        assert_eq!(2, service.store.fingers()[2].start);
+        assert_eq!(16, service.store.fingers()[2].node.id);
+        assert_eq!(14, service.store.fingers()[4].start);
+        assert_eq!(16, service.store.fingers()[4].node.id);
 
         service.id = 1;
         service.with_fingers_sized(6, nodes.clone());
-        assert_eq!(6, service.store.finger_table.len());
-
-        assert_eq!(16, service.store.finger_table[0].node.id);
-        assert_eq!(16, service.store.finger_table[1].node.id);
-        assert_eq!(5, service.store.finger_table[2].start);
-        assert_eq!(16, service.store.finger_table[2].node.id);
-        assert_eq!(17, service.store.finger_table[4].start);
-        assert_eq!(32, service.store.finger_table[4].node.id);
+        assert_eq!(6, service.store.finger_count());
+
+        assert_eq!(16, service.store.fingers()[0].node.id);
+        assert_eq!(16, service.store.fingers()[1].node.id);
+        assert_eq!(5, service.store.fingers()[2].start);
+        assert_eq!(16, service.store.fingers()[2].node.id);
+        assert_eq!(17, service.store.fingers()[4].start);
+        assert_eq!(32, service.store.fingers()[4].node.id);
+    }
+
+    #[test]
+    fn test_new_should_derive_id_from_address() {
+        let addr = SocketAddr::from(([127, 0, 0, 1], 42001));
+        let service: NodeService<MockClient> = NodeService::new(addr);
+        let other: NodeService<MockClient> = NodeService::new(SocketAddr::from(([127, 0, 0, 1], 42002)));
+
+        assert_eq!(service.node(), Node::new(addr));
+        assert_ne!(service.node().id, other.node().id);
+
+        let config = Config { ring: RingConfig::with_hasher(16, Sha1Hasher), ..Config::default() };
+        let service: NodeService<MockClient> = NodeService::with_config(addr, config.clone());
+        assert_eq!(service.node(), Node::with_ring(addr, &config.ring));
+        assert!(service.node().id < 1 << 16);
     }
 
     #[test]
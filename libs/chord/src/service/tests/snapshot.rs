@@ -0,0 +1,135 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use mockall::predicate;
+use crate::client::{ClientError, MockClient};
+use crate::{NodeService, RoutingSnapshot, ServiceError};
+use crate::service::tests;
+use crate::service::tests::{get_lock, MTX};
+
+fn service() -> NodeService<MockClient> {
+    NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42008)))
+}
+
+fn path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("chord-service-{}-{}.routing", std::process::id(), name))
+}
+
+fn snapshot() -> RoutingSnapshot {
+    RoutingSnapshot {
+        node: tests::node(8),
+        predecessor: Some(tests::node(2)),
+        successor_list: vec![tests::node(16), tests::node(21)],
+        fingers: vec![tests::node(16), tests::node(16), tests::node(21), tests::node(2)],
+    }
+}
+
+#[tokio::test]
+async fn it_should_rejoin_through_first_live_successor() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let mut client = MockClient::new();
+        match addr.port() {
+            42016 => {
+                client.expect_ping()
+                    .returning(|| Err(ClientError::ConnectionFailed(tests::node(16))));
+            }
+            42021 => {
+                client.expect_ping()
+                    .returning(|| Ok(()));
+                client.expect_find_successor()
+                    .with(predicate::eq(8))
+                    .returning(|_| Ok(tests::node(21)));
+            }
+            _ => {
+                client.expect_ping()
+                    .returning(|| Ok(()));
+            }
+        }
+
+        client
+    });
+
+    let path = path("rejoin");
+    snapshot().save(&path).unwrap();
+    let service = service();
+
+    let result = service.restore_routing(&path).await;
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(result.unwrap(), Some(tests::node(21)));
+    assert_eq!(service.store().successor(), tests::node(21));
+    assert_eq!(service.successor_list(), vec![tests::node(21)]);
+    assert_eq!(service.predecessor(), Some(tests::node(2)));
+
+    let fingers: Vec<u64> = service.store().fingers().iter().map(|finger| finger.node.id).collect();
+    assert_eq!(&fingers[..4], &[21, 8, 21, 2]);
+}
+
+#[tokio::test]
+async fn it_should_not_rejoin_when_no_peer_is_alive() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|_, id| {
+        let mut client = MockClient::new();
+        client.expect_ping()
+            .returning(move || Err(ClientError::Timeout(tests::node(id))));
+        client.expect_find_successor()
+            .never();
+
+        client
+    });
+
+    let path = path("dead");
+    snapshot().save(&path).unwrap();
+    let service = service();
+
+    let result = service.restore_routing(&path).await;
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(result.unwrap(), None);
+    assert_eq!(service.store().successor(), tests::node(8));
+    assert_eq!(service.predecessor(), None);
+}
+
+#[tokio::test]
+async fn missing_snapshot_should_not_restore_anything() {
+    let service = service();
+
+    let result = service.restore_routing(&path("missing")).await;
+
+    assert_eq!(result.unwrap(), None);
+    assert_eq!(service.store().successor(), tests::node(8));
+}
+
+#[tokio::test]
+async fn snapshot_of_another_node_should_be_rejected() {
+    let path = path("other");
+    let mut snapshot = snapshot();
+    snapshot.node = tests::node(9);
+    snapshot.save(&path).unwrap();
+
+    let result = service().restore_routing(&path).await;
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(result, Err(ServiceError::Storage(_))));
+}
+
+#[test]
+fn saved_routing_should_match_store() {
+    let path = path("save");
+    let service = service();
+    service.store().set_successor(tests::node(16));
+    service.store().set_predecessor(tests::node(2));
+
+    service.save_routing(&path).unwrap();
+    let loaded = RoutingSnapshot::load(&path).unwrap().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.node, tests::node(8));
+    assert_eq!(loaded.predecessor, Some(tests::node(2)));
+    assert_eq!(loaded.successor_list[0], tests::node(16));
+    assert_eq!(loaded.fingers.len(), service.store().finger_count());
+}
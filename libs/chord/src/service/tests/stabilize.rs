@@ -5,12 +5,12 @@ use crate::service::tests;
 use crate::{Node, NodeService};
 use crate::service::tests::{get_lock, MTX};
 
-#[test]
-fn stabilize_when_predecessor_is_between_node_and_successor_then_set_set_the_it_as_new_successor() {
-    let _m = get_lock(&MTX);
+#[tokio::test]
+async fn stabilize_when_predecessor_is_between_node_and_successor_then_set_set_the_it_as_new_successor() {
+    let _m = get_lock(&MTX).await;
     let ctx = MockClient::init_context();
 
-    ctx.expect().returning(|addr: SocketAddr| {
+    ctx.expect().returning(|addr: SocketAddr, _| {
         let mut client = MockClient::new();
         if addr.port() == 42016 {
             client.expect_predecessor()
@@ -21,6 +21,10 @@ fn stabilize_when_predecessor_is_between_node_and_successor_then_set_set_the_it_
         }
 
         if addr.port() == 42012 {
+            client.expect_successor_list()
+                .returning(|| {
+                    Ok(vec![])
+                });
             client.expect_notify()
                 .with(predicate::function(|n: &Node| n.id == 8))
                 .times(1)
@@ -31,28 +35,32 @@ fn stabilize_when_predecessor_is_between_node_and_successor_then_set_set_the_it_
         client
     });
 
-    let mut service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
     service.store.set_successor(tests::node(16));
 
     assert_eq!(service.store.successor().id, 16);
-    let result = service.stabilize();
+    let result = service.stabilize().await;
     assert!(result.is_ok());
 
     assert_eq!(service.store.successor().id, 12);
 }
 
-#[test]
-fn when_predecessor_is_not_between_node_and_successor_then_the_old_one_should_be_kept() {
-    let _m = get_lock(&MTX);
+#[tokio::test]
+async fn when_predecessor_is_not_between_node_and_successor_then_the_old_one_should_be_kept() {
+    let _m = get_lock(&MTX).await;
     let ctx = MockClient::init_context();
 
-    ctx.expect().returning(|addr: SocketAddr| {
+    ctx.expect().returning(|addr: SocketAddr, _| {
         let mut client = MockClient::new();
         if addr.port() == 42016 {
             client.expect_predecessor()
                 .returning(|| {
                     Ok(Some(tests::node(1)))
                 });
+            client.expect_successor_list()
+                .returning(|| {
+                    Ok(vec![])
+                });
             client.expect_notify()
                 .with(predicate::function(|n: &Node| n.id == 8))
                 .returning(|_| {
@@ -62,28 +70,32 @@ fn when_predecessor_is_not_between_node_and_successor_then_the_old_one_should_be
         client
     });
 
-    let mut service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
     service.store.set_successor(tests::node(16));
 
     assert_eq!(service.store.successor().id, 16);
-    let result = service.stabilize();
+    let result = service.stabilize().await;
     assert!(result.is_ok());
 
     assert_eq!(service.store.successor().id, 16);
 }
 
-#[test]
-fn when_getting_predecessor_fails_then_nothing_should_be_updated() {
-    let _m = get_lock(&MTX);
+#[tokio::test]
+async fn when_getting_predecessor_fails_then_nothing_should_be_updated() {
+    let _m = get_lock(&MTX).await;
     let ctx = MockClient::init_context();
 
-    ctx.expect().returning(|_| {
+    ctx.expect().returning(|_, _| {
         let mut client = MockClient::new();
         client.expect_predecessor()
             .returning(|| {
                 let error = ClientError::Unexpected("Test".to_string());
                 Err(error)
             });
+        client.expect_successor_list()
+            .returning(|| {
+                Ok(vec![])
+            });
         client.expect_notify()
             .with(predicate::function(|n: &Node| n.id == 8))
             .returning(|_| {
@@ -92,11 +104,11 @@ fn when_getting_predecessor_fails_then_nothing_should_be_updated() {
         client
     });
 
-    let mut service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
     service.store.set_successor(tests::node(16));
 
     assert_eq!(service.store.successor().id, 16);
-    let _ = service.stabilize();
+    let _ = service.stabilize().await;
 
     assert_eq!(service.store.successor().id, 16);
 }
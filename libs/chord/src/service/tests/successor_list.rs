@@ -0,0 +1,124 @@
+use std::net::SocketAddr;
+use mockall::predicate;
+use crate::client::{ClientError, MockClient};
+use crate::service::tests;
+use crate::{Config, Node, NodeService};
+use crate::service::tests::{get_lock, MTX};
+
+#[tokio::test]
+async fn stabilize_should_refresh_successor_list_from_successor() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let mut client = MockClient::new();
+        if addr.port() == 42016 {
+            client.expect_predecessor()
+                .returning(|| {
+                    Ok(Some(tests::node(8)))
+                });
+            client.expect_successor_list()
+                .returning(|| {
+                    Ok(vec![tests::node(21), tests::node(32), tests::node(40)])
+                });
+            client.expect_notify()
+                .with(predicate::function(|n: &Node| n.id == 8))
+                .returning(|_| {
+                    Ok(())
+                });
+        }
+        client
+    });
+
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
+    service.store.set_successor(tests::node(16));
+
+    service.stabilize().await.unwrap();
+
+    let ids: Vec<u64> = service.successor_list().iter().map(|n| n.id).collect();
+    assert_eq!(ids, vec![16, 21, 32]);
+}
+
+#[tokio::test]
+async fn when_successor_is_down_stabilize_should_fail_over_to_next_live_successor() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let mut client = MockClient::new();
+        if addr.port() == 42016 || addr.port() == 42021 {
+            client.expect_predecessor()
+                .times(1)
+                .returning(move || {
+                    Err(ClientError::ConnectionFailed(tests::node(addr.port() as u64 - 42000)))
+                });
+        }
+
+        if addr.port() == 42032 {
+            client.expect_predecessor()
+                .returning(|| {
+                    Ok(Some(tests::node(8)))
+                });
+            client.expect_successor_list()
+                .returning(|| {
+                    Ok(vec![tests::node(40), tests::node(48)])
+                });
+            client.expect_notify()
+                .with(predicate::function(|n: &Node| n.id == 8))
+                .returning(|_| {
+                    Ok(())
+                });
+        }
+        client
+    });
+
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
+    service.store.set_successor(tests::node(16));
+    service.store.update_successor_list(vec![tests::node(21), tests::node(32)]);
+
+    let result = service.stabilize().await;
+    assert!(result.is_ok());
+
+    assert_eq!(service.store.successor().id, 32);
+    let ids: Vec<u64> = service.successor_list().iter().map(|n| n.id).collect();
+    assert_eq!(ids, vec![32, 40, 48]);
+}
+
+#[tokio::test]
+async fn when_all_successors_are_down_stabilize_should_fail() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|addr: SocketAddr, _| {
+        let mut client = MockClient::new();
+        client.expect_predecessor()
+            .returning(move || {
+                Err(ClientError::ConnectionFailed(tests::node(addr.port() as u64 - 42000)))
+            });
+        client.expect_successor_list()
+            .returning(move || {
+                Err(ClientError::ConnectionFailed(tests::node(addr.port() as u64 - 42000)))
+            });
+        client.expect_notify()
+            .returning(move |_| {
+                Err(ClientError::ConnectionFailed(tests::node(addr.port() as u64 - 42000)))
+            });
+        client
+    });
+
+    let service: NodeService<MockClient> = NodeService::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42001)));
+    service.store.set_successor(tests::node(16));
+    service.store.update_successor_list(vec![tests::node(21)]);
+
+    let result = service.stabilize().await;
+    assert!(result.is_err());
+
+    assert_eq!(service.store.successor().id, 21);
+}
+
+#[test]
+#[should_panic(expected = "The successor list size must be at least 1")]
+fn node_should_reject_empty_successor_list() {
+    let config = Config { successor_list_size: 0, ..Config::default() };
+    NodeService::<MockClient>::with_config(SocketAddr::from(([127, 0, 0, 1], 42001)), config);
+}
@@ -0,0 +1,156 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+use mockall::predicate;
+use crate::client::MockClient;
+use crate::{Config, MaintenanceConfig, Node, NodeService, RingConfig, VirtualHost};
+use crate::service::tests::{get_lock, MTX};
+
+fn host_addr() -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], 42100))
+}
+
+#[test]
+fn host_should_run_given_number_of_virtual_nodes() {
+    let host: VirtualHost<MockClient> = VirtualHost::new(host_addr(), 4, Config::default());
+
+    let ring = RingConfig::default();
+    let expected: Vec<u64> = (0..4).map(|i| Node::virtual_node(host_addr(), i, &ring).id()).collect();
+    assert_eq!(host.ids(), expected);
+
+    let mut ids = host.ids();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), 4);
+    assert!(host.nodes().iter().all(|node| node.node().addr() == host_addr()));
+}
+
+#[test]
+fn host_should_skip_virtual_nodes_with_duplicate_id() {
+    let config = Config { ring: RingConfig::new(2), ..Config::default() };
+    let host: VirtualHost<MockClient> = VirtualHost::new(host_addr(), 8, config);
+
+    let mut ids = host.ids();
+    assert!(ids.len() <= 4);
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), host.nodes().len());
+}
+
+#[test]
+fn host_should_dispatch_by_virtual_node_id() {
+    let host: VirtualHost<MockClient> = VirtualHost::new(host_addr(), 3, Config::default());
+    let id = host.ids()[1];
+
+    assert_eq!(host.node(id).unwrap().node().id(), id);
+    assert!(host.node(id.wrapping_add(1)).is_none());
+    assert!(host.owns(&Node::with_id(id, host_addr())));
+    assert!(!host.owns(&Node::with_id(id, SocketAddr::from(([127, 0, 0, 1], 42101)))));
+}
+
+#[test]
+#[should_panic]
+fn host_should_reject_zero_virtual_nodes() {
+    VirtualHost::<MockClient>::new(host_addr(), 0, Config::default());
+}
+
+#[test]
+fn successor_hosts_should_skip_nodes_of_same_host() {
+    let other = SocketAddr::from(([127, 0, 0, 1], 42200));
+    let third = SocketAddr::from(([127, 0, 0, 1], 42300));
+
+    let service: NodeService<MockClient> = NodeService::with_id_and_config(8, host_addr(), Config {
+        successor_list_size: 5,
+        ..Config::default()
+    });
+    service.store.update_successor_list(vec![
+        Node::with_id(10, other),
+        Node::with_id(12, host_addr()),
+        Node::with_id(14, other),
+        Node::with_id(16, third),
+        Node::with_id(18, third),
+    ]);
+
+    let hosts: Vec<(u64, SocketAddr)> = service.successor_hosts().iter().map(|n| (n.id(), n.addr())).collect();
+    assert_eq!(hosts, vec![(10, other), (16, third)]);
+}
+
+#[tokio::test]
+async fn host_should_join_ring_with_all_virtual_nodes() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    ctx.expect().returning(|_, _| {
+        let mut client = MockClient::new();
+        client.expect_find_successor()
+            .returning(|id| {
+                Ok(Node::with_id(id.wrapping_add(1), SocketAddr::from(([127, 0, 0, 1], 42200))))
+            });
+        client
+    });
+
+    let host: VirtualHost<MockClient> = VirtualHost::new(host_addr(), 3, Config::default());
+    host.join(Node::with_id(1, SocketAddr::from(([127, 0, 0, 1], 42200)))).await.unwrap();
+
+    for node in host.nodes() {
+        assert_eq!(node.successor_list()[0].id(), node.node().id().wrapping_add(1));
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn host_maintenance_should_stabilize_all_virtual_nodes() {
+    let _m = get_lock(&MTX).await;
+    let ctx = MockClient::init_context();
+
+    let host: VirtualHost<MockClient> = VirtualHost::new(host_addr(), 3, Config::default());
+    let ids = host.ids();
+
+    ctx.expect().returning(move |_, _| {
+        let mut client = MockClient::new();
+        client.expect_predecessor()
+            .returning(|| {
+                Ok(None)
+            });
+        client.expect_successor_list()
+            .returning(|| {
+                Ok(vec![])
+            });
+        client.expect_ping()
+            .returning(|| {
+                Ok(())
+            });
+        client.expect_find_successor()
+            .returning(|_| {
+                Ok(Node::with_id(1, SocketAddr::from(([127, 0, 0, 1], 42200))))
+            });
+        let ids = ids.clone();
+        client.expect_notify()
+            .with(predicate::function(move |n: &Node| ids.contains(&n.id())))
+            .times(1)
+            .returning(|_| {
+                Ok(())
+            });
+        client
+    });
+
+    let mut maintenance = host.maintenance(MaintenanceConfig {
+        stabilize_interval: Duration::from_secs(1),
+        fix_fingers_interval: Duration::from_secs(10),
+        check_predecessor_interval: Duration::from_secs(10),
+        sync_replicas_interval: Duration::from_secs(10),
+        anti_entropy_interval: Duration::from_secs(10),
+        replay_hints_interval: Duration::from_secs(10),
+        collect_tombstones_interval: Duration::from_secs(10),
+        snapshot_interval: Duration::from_secs(10),
+        jitter: Duration::ZERO,
+    });
+    assert_eq!(maintenance.nodes().len(), 3);
+
+    maintenance.start();
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    maintenance.stop();
+
+    for node in host.nodes() {
+        assert_eq!(node.store.predecessor(), None);
+        assert_eq!(node.successor_list()[0], node.node());
+    }
+}
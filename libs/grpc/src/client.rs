@@ -0,0 +1,217 @@
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Duration;
+use async_trait::async_trait;
+use chord_rs::{Client, ClientError, Entry, MerkleTree, Node, Versioned};
+use tonic::{Code, Request, Response, Status};
+use tonic::transport::Channel;
+use crate::convert::{optional_node, required_node, InvalidMessage};
+use crate::pool::CHANNELS;
+use crate::proto;
+use crate::proto::chord_client::ChordClient;
+
+/// Maximum time to wait for the response of a request
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// [`Client`] talking to the [`ChordService`](crate::ChordService) of a node over gRPC
+///
+/// A client is created for every request to another node, so the clients don't own their
+/// connection. All the clients of a host share a single channel, which is opened on the first
+/// request and kept for the lifetime of the process.
+///
+/// The failures are mapped to the [`ClientError`] variants the node service reacts to: a host
+/// which can't be connected to is [`ClientError::ConnectionFailed`], a request without a
+/// response within [`REQUEST_TIMEOUT`] is [`ClientError::Timeout`] and a request the node refused
+/// to handle is [`ClientError::Rejected`].
+///
+/// > **Note**
+/// >
+/// > The client has to be created within a tokio runtime, because the channel of a new host is
+/// > driven by a background task.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn run() {
+/// use chord_rs::{Node, NodeService};
+/// use grpc::GrpcClient;
+///
+/// let service: NodeService<GrpcClient> = NodeService::new("127.0.0.1:42001".parse().unwrap());
+/// service.join(Node::new("127.0.0.1:42000".parse().unwrap())).await.unwrap();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct GrpcClient {
+    node: Node,
+    client: ChordClient<Channel>,
+}
+
+impl GrpcClient {
+    /// Send the request and wait for the response, at most for [`REQUEST_TIMEOUT`]
+    async fn call<T, F, Fut>(&self, request: F) -> Result<T, ClientError>
+        where F: FnOnce(ChordClient<Channel>) -> Fut,
+              Fut: Future<Output = Result<Response<T>, Status>> {
+        match tokio::time::timeout(REQUEST_TIMEOUT, request(self.client.clone())).await {
+            Ok(Ok(response)) => Ok(response.into_inner()),
+            Ok(Err(status)) => Err(self.error(status)),
+            Err(_) => Err(ClientError::Timeout(self.node.clone())),
+        }
+    }
+
+    fn error(&self, status: Status) -> ClientError {
+        match status.code() {
+            Code::Unavailable => ClientError::ConnectionFailed(self.node.clone()),
+            Code::DeadlineExceeded | Code::Cancelled => ClientError::Timeout(self.node.clone()),
+            Code::FailedPrecondition => ClientError::Rejected(status.message().to_string()),
+            Code::Unimplemented | Code::InvalidArgument => ClientError::ProtocolMismatch(status.message().to_string()),
+            Code::Internal | Code::Unknown => ClientError::Unexpected(status.message().to_string()),
+            _ => ClientError::Transport(Box::new(status)),
+        }
+    }
+
+    fn node_request(&self) -> Request<proto::NodeRequest> {
+        request(proto::NodeRequest { node_id: self.node.id() })
+    }
+
+    fn range_request(&self, from: u64, to: u64, after: Option<Vec<u8>>, limit: usize) -> Request<proto::RangeRequest> {
+        request(proto::RangeRequest { node_id: self.node.id(), from, to, after, limit: limit as u64 })
+    }
+}
+
+/// Wrap the message into a request, the deadline tells the node when the caller gives up
+fn request<T>(message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    request.set_timeout(REQUEST_TIMEOUT);
+    request
+}
+
+fn mismatch(err: InvalidMessage) -> ClientError {
+    ClientError::ProtocolMismatch(err.to_string())
+}
+
+fn node(response: proto::NodeResponse) -> Result<Node, ClientError> {
+    required_node(response.node, "node").map_err(mismatch)
+}
+
+fn entries(response: proto::EntriesResponse) -> Vec<Entry> {
+    response.entries.into_iter().map(Entry::from).collect()
+}
+
+#[async_trait]
+impl Client for GrpcClient {
+    fn init(addr: SocketAddr, id: u64) -> Self {
+        Self { node: Node::with_id(id, addr), client: ChordClient::new(CHANNELS.channel(addr)) }
+    }
+
+    async fn find_successor(&self, id: u64) -> Result<Node, ClientError> {
+        let message = proto::IdRequest { node_id: self.node.id(), id };
+        node(self.call(|mut client| async move { client.find_successor(request(message)).await }).await?)
+    }
+
+    async fn closest_preceding_node(&self, id: u64) -> Result<Node, ClientError> {
+        let message = proto::IdRequest { node_id: self.node.id(), id };
+        node(self.call(|mut client| async move { client.closest_preceding_node(request(message)).await }).await?)
+    }
+
+    async fn successor(&self) -> Result<Node, ClientError> {
+        let request = self.node_request();
+        node(self.call(|mut client| async move { client.successor(request).await }).await?)
+    }
+
+    async fn successor_list(&self) -> Result<Vec<Node>, ClientError> {
+        let request = self.node_request();
+        let response = self.call(|mut client| async move { client.successor_list(request).await }).await?;
+
+        response.nodes.into_iter().map(|node| Node::try_from(node).map_err(mismatch)).collect()
+    }
+
+    async fn predecessor(&self) -> Result<Option<Node>, ClientError> {
+        let request = self.node_request();
+        let response = self.call(|mut client| async move { client.predecessor(request).await }).await?;
+
+        optional_node(response.predecessor).map_err(mismatch)
+    }
+
+    async fn notify(&self, predecessor: Node) -> Result<(), ClientError> {
+        let message = proto::NotifyRequest { node_id: self.node.id(), predecessor: Some(predecessor.into()) };
+        self.call(|mut client| async move { client.notify(request(message)).await }).await?;
+
+        Ok(())
+    }
+
+    async fn successor_leaving(&self, leaving: Node, successor: Node) -> Result<(), ClientError> {
+        let message = proto::SuccessorLeavingRequest {
+            node_id: self.node.id(),
+            leaving: Some(leaving.into()),
+            successor: Some(successor.into()),
+        };
+        self.call(|mut client| async move { client.successor_leaving(request(message)).await }).await?;
+
+        Ok(())
+    }
+
+    async fn predecessor_leaving(&self, leaving: Node, predecessor: Option<Node>) -> Result<(), ClientError> {
+        let message = proto::PredecessorLeavingRequest {
+            node_id: self.node.id(),
+            leaving: Some(leaving.into()),
+            predecessor: predecessor.map(proto::Node::from),
+        };
+        self.call(|mut client| async move { client.predecessor_leaving(request(message)).await }).await?;
+
+        Ok(())
+    }
+
+    async fn ping(&self) -> Result<(), ClientError> {
+        let request = self.node_request();
+        self.call(|mut client| async move { client.ping(request).await }).await?;
+
+        Ok(())
+    }
+
+    async fn put(&self, key: Vec<u8>, version: Versioned) -> Result<(), ClientError> {
+        let message = proto::PutRequest { node_id: self.node.id(), key, version: Some(version.into()) };
+        self.call(|mut client| async move { client.put(request(message)).await }).await?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: Vec<u8>) -> Result<Vec<Versioned>, ClientError> {
+        let message = proto::KeyRequest { node_id: self.node.id(), key };
+        let response = self.call(|mut client| async move { client.get(request(message)).await }).await?;
+
+        Ok(response.versions.into_iter().map(Versioned::from).collect())
+    }
+
+    async fn transfer_keys(&self, from: u64, to: u64, after: Option<Vec<u8>>) -> Result<Vec<Entry>, ClientError> {
+        let request = self.range_request(from, to, after, 0);
+        Ok(entries(self.call(|mut client| async move { client.transfer_keys(request).await }).await?))
+    }
+
+    async fn scan(&self, from: u64, to: u64, after: Option<Vec<u8>>, limit: usize) -> Result<Vec<Entry>, ClientError> {
+        let request = self.range_request(from, to, after, limit);
+        Ok(entries(self.call(|mut client| async move { client.scan(request).await }).await?))
+    }
+
+    async fn confirm_transfer(&self, entries: Vec<Entry>) -> Result<(), ClientError> {
+        let entries = entries.into_iter().map(proto::Entry::from).collect();
+        let message = proto::EntriesRequest { node_id: self.node.id(), entries };
+        self.call(|mut client| async move { client.confirm_transfer(request(message)).await }).await?;
+
+        Ok(())
+    }
+
+    async fn replicate(&self, entries: Vec<Entry>) -> Result<(), ClientError> {
+        let entries = entries.into_iter().map(proto::Entry::from).collect();
+        let message = proto::EntriesRequest { node_id: self.node.id(), entries };
+        self.call(|mut client| async move { client.replicate(request(message)).await }).await?;
+
+        Ok(())
+    }
+
+    async fn merkle_tree(&self, from: u64, to: u64) -> Result<MerkleTree, ClientError> {
+        let request = self.range_request(from, to, None, 0);
+        let response = self.call(|mut client| async move { client.merkle_tree(request).await }).await?;
+
+        MerkleTree::try_from(response).map_err(mismatch)
+    }
+}
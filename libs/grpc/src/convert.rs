@@ -0,0 +1,171 @@
+use std::error::Error;
+use std::fmt::Display;
+use chord_rs::{Entry, MerkleTree, Node, Versioned};
+use crate::proto;
+
+/// The deepest Merkle tree accepted from the wire, deeper trees would not fit in memory anyway
+const MAX_MERKLE_TREE_DEPTH: u32 = 24;
+
+/// Error returned when a message received from the wire can't be converted
+#[derive(Debug)]
+pub struct InvalidMessage(String);
+
+impl InvalidMessage {
+    /// Error of a message field which wasn't set
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - The name of the field
+    pub(crate) fn missing(field: &str) -> Self {
+        Self(format!("Missing field {}", field))
+    }
+}
+
+impl Display for InvalidMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid message: {}", self.0)
+    }
+}
+
+impl Error for InvalidMessage {}
+
+impl From<&Node> for proto::Node {
+    fn from(node: &Node) -> Self {
+        Self { id: node.id(), addr: node.addr().to_string() }
+    }
+}
+
+impl From<Node> for proto::Node {
+    fn from(node: Node) -> Self {
+        Self::from(&node)
+    }
+}
+
+impl TryFrom<proto::Node> for Node {
+    type Error = InvalidMessage;
+
+    fn try_from(node: proto::Node) -> Result<Self, Self::Error> {
+        let addr = node.addr.parse()
+            .map_err(|_| InvalidMessage(format!("Invalid address {} of node {}", node.addr, node.id)))?;
+
+        Ok(Node::with_id(node.id, addr))
+    }
+}
+
+impl From<Versioned> for proto::Versioned {
+    fn from(version: Versioned) -> Self {
+        let clock = version.clock.counters().iter().map(|(node_id, counter)| (*node_id, *counter)).collect();
+        Self { value: version.value, clock }
+    }
+}
+
+impl From<proto::Versioned> for Versioned {
+    fn from(version: proto::Versioned) -> Self {
+        Versioned { value: version.value, clock: version.clock.into_iter().collect() }
+    }
+}
+
+impl From<Entry> for proto::Entry {
+    fn from((key, versions): Entry) -> Self {
+        Self { key, versions: versions.into_iter().map(proto::Versioned::from).collect() }
+    }
+}
+
+impl From<proto::Entry> for Entry {
+    fn from(entry: proto::Entry) -> Self {
+        (entry.key, entry.versions.into_iter().map(Versioned::from).collect())
+    }
+}
+
+impl From<MerkleTree> for proto::MerkleTreeResponse {
+    fn from(tree: MerkleTree) -> Self {
+        Self {
+            bits: tree.bits() as u32,
+            from: tree.from(),
+            to: tree.to(),
+            depth: tree.depth() as u32,
+            hashes: tree.hashes().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<proto::MerkleTreeResponse> for MerkleTree {
+    type Error = InvalidMessage;
+
+    fn try_from(tree: proto::MerkleTreeResponse) -> Result<Self, Self::Error> {
+        if !(1..=64).contains(&tree.bits) || tree.depth > MAX_MERKLE_TREE_DEPTH {
+            return Err(InvalidMessage(format!("Invalid Merkle tree of {} bits and depth {}", tree.bits, tree.depth)));
+        }
+        if tree.hashes.len() != (2 << tree.depth) - 1 {
+            return Err(InvalidMessage(format!("Merkle tree of depth {} with {} hashes", tree.depth, tree.hashes.len())));
+        }
+
+        Ok(MerkleTree::from_parts(tree.bits as u8, tree.from, tree.to, tree.depth as u8, tree.hashes))
+    }
+}
+
+/// Convert an optional node of a message
+///
+/// # Arguments
+///
+/// * `node` - The node field of the message
+pub(crate) fn optional_node(node: Option<proto::Node>) -> Result<Option<Node>, InvalidMessage> {
+    node.map(Node::try_from).transpose()
+}
+
+/// Convert a node field which has to be set
+///
+/// # Arguments
+///
+/// * `node` - The node field of the message
+/// * `field` - The name of the field
+pub(crate) fn required_node(node: Option<proto::Node>, field: &str) -> Result<Node, InvalidMessage> {
+    optional_node(node)?.ok_or_else(|| InvalidMessage::missing(field))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use chord_rs::VectorClock;
+    use super::*;
+
+    #[test]
+    fn it_should_convert_node() {
+        let node = Node::with_id(8, SocketAddr::from(([127, 0, 0, 1], 42008)));
+
+        let message = proto::Node::from(&node);
+        assert_eq!(message.addr, "127.0.0.1:42008");
+        assert_eq!(Node::try_from(message).unwrap(), node);
+
+        let invalid = proto::Node { id: 8, addr: "localhost".to_string() };
+        assert!(Node::try_from(invalid).is_err());
+        assert!(required_node(None, "node").is_err());
+    }
+
+    #[test]
+    fn it_should_convert_versions() {
+        let mut clock = VectorClock::new();
+        clock.increment(8);
+        clock.increment(16);
+        let versions = vec![
+            Versioned::new(b"value".to_vec(), clock.clone()),
+            Versioned::tombstone(clock),
+            Versioned::new(Vec::new(), VectorClock::new()),
+        ];
+        let entry: Entry = (b"key".to_vec(), versions);
+
+        let message = proto::Entry::from(entry.clone());
+        assert_eq!(Entry::from(message), entry);
+    }
+
+    #[test]
+    fn it_should_reject_invalid_merkle_tree() {
+        let tree = proto::MerkleTreeResponse { bits: 64, from: 0, to: 0, depth: 1, hashes: vec![0, 0, 0] };
+        assert_eq!(MerkleTree::try_from(tree.clone()).unwrap().root(), 0);
+
+        let truncated = proto::MerkleTreeResponse { hashes: vec![0, 0], ..tree.clone() };
+        assert!(MerkleTree::try_from(truncated).is_err());
+        let deep = proto::MerkleTreeResponse { depth: 63, ..tree };
+        assert!(MerkleTree::try_from(deep).is_err());
+    }
+}
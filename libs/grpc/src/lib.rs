@@ -1,14 +1,23 @@
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
-}
+//! gRPC transport of the chord nodes
+//!
+//! The wire protocol is defined in `proto/chord.proto`, every operation of the
+//! [`Client`](chord_rs::Client) trait is an RPC of the `chord.v1.Chord` service. The
+//! [`ChordService`] serves the RPCs of the nodes running on a host and the [`GrpcClient`] sends
+//! them to the other nodes.
 
+mod client;
+mod convert;
+mod pool;
+mod server;
 #[cfg(test)]
-mod tests {
-    use super::*;
+mod tests;
+
+pub use client::{GrpcClient, REQUEST_TIMEOUT};
+pub use convert::InvalidMessage;
+pub use pool::CONNECT_TIMEOUT;
+pub use server::ChordService;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
+/// The messages and the generated client and server of the `chord.v1` protocol
+pub mod proto {
+    tonic::include_proto!("chord.v1");
 }
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{LazyLock, Mutex, PoisonError};
+use std::time::Duration;
+use tonic::transport::{Channel, Endpoint};
+
+/// Maximum time to establish the connection to a host
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The channels shared by all the [`GrpcClient`](crate::GrpcClient)s of the process
+pub(crate) static CHANNELS: LazyLock<ChannelPool> = LazyLock::new(ChannelPool::new);
+
+/// Channels to the hosts of the ring, by address
+///
+/// A channel multiplexes all the requests to a host over a single HTTP/2 connection and it
+/// reconnects on its own once the connection is lost, so a channel is kept for as long as the
+/// process runs. The virtual nodes of a host share the channel of the host.
+#[derive(Default)]
+pub(crate) struct ChannelPool {
+    channels: Mutex<HashMap<SocketAddr, Channel>>,
+}
+
+impl ChannelPool {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the channel to the host, it's created on first use
+    ///
+    /// The channel connects lazily, on the first request sent through it.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address of the host
+    pub(crate) fn channel(&self, addr: SocketAddr) -> Channel {
+        let mut channels = self.channels.lock().unwrap_or_else(PoisonError::into_inner);
+        channels.entry(addr)
+            .or_insert_with(|| {
+                log::debug!("Opening channel to {}", addr);
+                Endpoint::from_shared(format!("http://{}", addr))
+                    .expect("The URI of a socket address is valid")
+                    .connect_timeout(CONNECT_TIMEOUT)
+                    .connect_lazy()
+            })
+            .clone()
+    }
+
+    /// Get the number of hosts with a channel
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.channels.lock().unwrap_or_else(PoisonError::into_inner).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn it_should_reuse_channel_of_host() {
+        let pool = ChannelPool::new();
+        let host = SocketAddr::from(([127, 0, 0, 1], 42001));
+
+        pool.channel(host);
+        pool.channel(host);
+        assert_eq!(pool.len(), 1);
+
+        pool.channel(SocketAddr::from(([127, 0, 0, 1], 42002)));
+        assert_eq!(pool.len(), 2);
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+use chord_rs::{Client, KvStore, NodeHandle, ServiceError, StorageError};
+use crate::convert::{optional_node, required_node, InvalidMessage};
+use crate::proto;
+use crate::proto::chord_server::{Chord, ChordServer};
+
+/// The gRPC service of the nodes running on a host
+///
+/// The chord RPCs are dispatched to the [`NodeHandle`] of the node the request is meant for, the
+/// key-value RPCs to its [`KvStore`]. A host running several virtual nodes serves all of them
+/// with a single service.
+///
+/// A lookup for a node the host doesn't serve is answered by the served node with the lowest id,
+/// so a peer which only knows the address of the host, e.g. from [`Node::new`](chord_rs::Node::new),
+/// can join the ring through any of the virtual nodes. All the other RPCs are rejected for such a
+/// node.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn run<C: chord_rs::Client + 'static>(host: chord_rs::VirtualHost<C>) {
+/// use grpc::ChordService;
+///
+/// let service = ChordService::for_nodes(host.nodes().to_vec());
+/// tonic::transport::Server::builder()
+///     .add_service(service.into_server())
+///     .serve(host.addr())
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+pub struct ChordService<C: Client + 'static> {
+    nodes: HashMap<u64, NodeHandle<C>>,
+    stores: HashMap<u64, Arc<KvStore<C>>>,
+}
+
+impl<C: Client + 'static> ChordService<C> {
+    /// Create a new service for a single node
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node to serve
+    pub fn new(node: impl Into<NodeHandle<C>>) -> Self {
+        Self::for_nodes(vec![node.into()])
+    }
+
+    /// Create a new service for all the given nodes, e.g. the virtual nodes of a host
+    ///
+    /// # Arguments
+    ///
+    /// * `nodes` - The nodes to serve
+    pub fn for_nodes(nodes: Vec<NodeHandle<C>>) -> Self {
+        let nodes = nodes.into_iter().map(|node| (node.node().id(), node)).collect();
+        Self { nodes, stores: HashMap::new() }
+    }
+
+    /// Serve the key-value RPCs of the node of the store
+    ///
+    /// The node of the store is served as well, if it wasn't already.
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The key-value store of one of the nodes
+    pub fn with_store(mut self, store: Arc<KvStore<C>>) -> Self {
+        let id = store.node().node().id();
+        self.nodes.entry(id).or_insert_with(|| store.node().clone());
+        self.stores.insert(id, store);
+        self
+    }
+
+    /// Wrap the service into a tonic server, ready to be added to a
+    /// [`Server`](tonic::transport::Server)
+    pub fn into_server(self) -> ChordServer<Self> {
+        ChordServer::new(self)
+    }
+
+    fn node(&self, id: u64) -> Result<&NodeHandle<C>, Status> {
+        self.nodes.get(&id)
+            .ok_or_else(|| Status::failed_precondition(format!("Node {} is not served by this host", id)))
+    }
+
+    /// Get the node the lookup is meant for, or any served node when the id isn't known, since
+    /// every node of the ring can answer a lookup
+    fn lookup_node(&self, id: u64) -> Result<&NodeHandle<C>, Status> {
+        self.node(id).or_else(|err| self.nodes.iter().min_by_key(|(id, _)| **id).map(|(_, node)| node).ok_or(err))
+    }
+
+    fn store(&self, id: u64) -> Result<&KvStore<C>, Status> {
+        self.node(id)?;
+        self.stores.get(&id)
+            .map(|store| store.as_ref())
+            .ok_or_else(|| Status::failed_precondition(format!("Node {} doesn't serve a key-value store", id)))
+    }
+}
+
+/// Map the error of a node to the status of the response
+///
+/// The failures of other nodes reached while handling the request are internal errors, so the
+/// caller doesn't take the node which answered for unreachable.
+fn status(err: ServiceError) -> Status {
+    match err {
+        ServiceError::Rejected(reason) => Status::failed_precondition(reason),
+        ServiceError::InvalidArgument(message) => Status::invalid_argument(message),
+        err => Status::internal(err.to_string()),
+    }
+}
+
+fn storage_status(err: StorageError) -> Status {
+    status(err.into())
+}
+
+fn invalid(err: InvalidMessage) -> Status {
+    Status::invalid_argument(err.to_string())
+}
+
+fn empty() -> Result<Response<proto::Empty>, Status> {
+    Ok(Response::new(proto::Empty {}))
+}
+
+fn entries(entries: Vec<chord_rs::Entry>) -> Response<proto::EntriesResponse> {
+    Response::new(proto::EntriesResponse { entries: entries.into_iter().map(proto::Entry::from).collect() })
+}
+
+#[tonic::async_trait]
+impl<C: Client + 'static> Chord for ChordService<C> {
+    async fn find_successor(&self, request: Request<proto::IdRequest>) -> Result<Response<proto::NodeResponse>, Status> {
+        let request = request.into_inner();
+        let successor = self.lookup_node(request.node_id)?.find_successor(request.id).await.map_err(status)?;
+
+        Ok(Response::new(proto::NodeResponse { node: Some(successor.into()) }))
+    }
+
+    async fn closest_preceding_node(&self, request: Request<proto::IdRequest>)
+        -> Result<Response<proto::NodeResponse>, Status> {
+        let request = request.into_inner();
+        let node = self.lookup_node(request.node_id)?.closest_preceding_node(request.id);
+
+        Ok(Response::new(proto::NodeResponse { node: Some(node.into()) }))
+    }
+
+    async fn successor(&self, request: Request<proto::NodeRequest>) -> Result<Response<proto::NodeResponse>, Status> {
+        let successor = self.node(request.get_ref().node_id)?.successor_list().first().cloned()
+            .ok_or_else(|| Status::failed_precondition("The node has no successor"))?;
+
+        Ok(Response::new(proto::NodeResponse { node: Some(successor.into()) }))
+    }
+
+    async fn successor_list(&self, request: Request<proto::NodeRequest>)
+        -> Result<Response<proto::NodesResponse>, Status> {
+        let successors = self.node(request.get_ref().node_id)?.successor_list();
+
+        Ok(Response::new(proto::NodesResponse { nodes: successors.into_iter().map(proto::Node::from).collect() }))
+    }
+
+    async fn predecessor(&self, request: Request<proto::NodeRequest>)
+        -> Result<Response<proto::PredecessorResponse>, Status> {
+        let predecessor = self.node(request.get_ref().node_id)?.predecessor();
+
+        Ok(Response::new(proto::PredecessorResponse { predecessor: predecessor.map(proto::Node::from) }))
+    }
+
+    async fn notify(&self, request: Request<proto::NotifyRequest>) -> Result<Response<proto::Empty>, Status> {
+        let request = request.into_inner();
+        let predecessor = required_node(request.predecessor, "predecessor").map_err(invalid)?;
+        self.node(request.node_id)?.notify(predecessor);
+
+        empty()
+    }
+
+    async fn successor_leaving(&self, request: Request<proto::SuccessorLeavingRequest>)
+        -> Result<Response<proto::Empty>, Status> {
+        let request = request.into_inner();
+        let leaving = required_node(request.leaving, "leaving").map_err(invalid)?;
+        let successor = required_node(request.successor, "successor").map_err(invalid)?;
+        self.node(request.node_id)?.successor_leaving(leaving, successor);
+
+        empty()
+    }
+
+    async fn predecessor_leaving(&self, request: Request<proto::PredecessorLeavingRequest>)
+        -> Result<Response<proto::Empty>, Status> {
+        let request = request.into_inner();
+        let leaving = required_node(request.leaving, "leaving").map_err(invalid)?;
+        let predecessor = optional_node(request.predecessor).map_err(invalid)?;
+        self.node(request.node_id)?.predecessor_leaving(leaving, predecessor);
+
+        empty()
+    }
+
+    async fn ping(&self, request: Request<proto::NodeRequest>) -> Result<Response<proto::Empty>, Status> {
+        self.node(request.get_ref().node_id)?;
+
+        empty()
+    }
+
+    async fn put(&self, request: Request<proto::PutRequest>) -> Result<Response<proto::Empty>, Status> {
+        let request = request.into_inner();
+        let version = request.version.ok_or_else(|| invalid(InvalidMessage::missing("version")))?;
+        self.store(request.node_id)?.put_local(request.key, version.into()).map_err(storage_status)?;
+
+        empty()
+    }
+
+    async fn get(&self, request: Request<proto::KeyRequest>) -> Result<Response<proto::VersionsResponse>, Status> {
+        let request = request.into_inner();
+        let versions = self.store(request.node_id)?.get_local(&request.key).map_err(storage_status)?;
+
+        Ok(Response::new(proto::VersionsResponse { versions: versions.into_iter().map(proto::Versioned::from).collect() }))
+    }
+
+    async fn transfer_keys(&self, request: Request<proto::RangeRequest>)
+        -> Result<Response<proto::EntriesResponse>, Status> {
+        let request = request.into_inner();
+        let batch = self.store(request.node_id)?
+            .transfer_keys_local(request.from, request.to, request.after.as_deref())
+            .map_err(storage_status)?;
+
+        Ok(entries(batch))
+    }
+
+    async fn confirm_transfer(&self, request: Request<proto::EntriesRequest>) -> Result<Response<proto::Empty>, Status> {
+        let request = request.into_inner();
+        let transferred = request.entries.into_iter().map(chord_rs::Entry::from).collect();
+        self.store(request.node_id)?.confirm_transfer_local(transferred).map_err(storage_status)?;
+
+        empty()
+    }
+
+    async fn replicate(&self, request: Request<proto::EntriesRequest>) -> Result<Response<proto::Empty>, Status> {
+        let request = request.into_inner();
+        let batch = request.entries.into_iter().map(chord_rs::Entry::from).collect();
+        self.store(request.node_id)?.replicate_local(batch).map_err(storage_status)?;
+
+        empty()
+    }
+
+    async fn merkle_tree(&self, request: Request<proto::RangeRequest>)
+        -> Result<Response<proto::MerkleTreeResponse>, Status> {
+        let request = request.into_inner();
+        let tree = self.store(request.node_id)?.merkle_tree_local(request.from, request.to).map_err(storage_status)?;
+
+        Ok(Response::new(tree.into()))
+    }
+
+    async fn scan(&self, request: Request<proto::RangeRequest>) -> Result<Response<proto::EntriesResponse>, Status> {
+        let request = request.into_inner();
+        let limit = usize::try_from(request.limit).unwrap_or(usize::MAX);
+        let page = self.store(request.node_id)?
+            .scan_local(request.from, request.to, request.after.as_deref(), limit)
+            .map_err(storage_status)?;
+
+        Ok(entries(page))
+    }
+}
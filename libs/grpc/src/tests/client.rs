@@ -0,0 +1,75 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use chord_rs::{Client, ClientError, Config, KvStore, Node, NodeService, VirtualHost};
+use tokio::net::TcpListener;
+use crate::{ChordService, GrpcClient};
+use crate::tests::serve_on;
+
+/// Start a node serving a key-value store over gRPC
+async fn host() -> Arc<KvStore<GrpcClient>> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let service: NodeService<GrpcClient> = NodeService::new(listener.local_addr().unwrap());
+    let store = Arc::new(KvStore::new(service));
+    serve_on(listener, ChordService::new(store.node().clone()).with_store(store.clone()));
+
+    store
+}
+
+/// Get an address nothing listens on
+async fn closed_addr() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    listener.local_addr().unwrap()
+}
+
+#[tokio::test]
+async fn nodes_should_form_ring_over_grpc() {
+    let first = host().await;
+    let second = host().await;
+    let (first_node, second_node) = (first.node().node(), second.node().node());
+
+    second.join(first_node.clone()).await.unwrap();
+    second.node().stabilize().await.unwrap();
+    first.node().stabilize().await.unwrap();
+
+    assert_eq!(first.node().successor_list()[0], second_node);
+    assert_eq!(second.node().successor_list()[0], first_node);
+    assert_eq!(first.node().predecessor(), Some(second_node.clone()));
+    assert_eq!(second.node().predecessor(), Some(first_node.clone()));
+
+    first.put(b"key", b"value".to_vec()).await.unwrap();
+    let versions = second.get(b"key").await.unwrap();
+    assert_eq!(versions.value(), Some(b"value".as_slice()));
+
+    let page = second.scan(0, 0, 10, None).await.unwrap();
+    assert_eq!(page.entries.len(), 1);
+}
+
+#[tokio::test]
+async fn node_should_join_virtual_host_by_address() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let virtual_host: VirtualHost<GrpcClient> = VirtualHost::new(listener.local_addr().unwrap(), 4, Config::default());
+    serve_on(listener, ChordService::for_nodes(virtual_host.nodes().to_vec()));
+
+    let joining: NodeService<GrpcClient> = NodeService::new(closed_addr().await);
+    joining.join(Node::new(virtual_host.addr())).await.unwrap();
+
+    let successor = joining.successor_list()[0].clone();
+    assert!(virtual_host.owns(&successor));
+}
+
+#[tokio::test]
+async fn client_should_map_failures() {
+    let store = host().await;
+    let node = store.node().node();
+
+    let client = node.client::<GrpcClient>();
+    client.ping().await.unwrap();
+    assert_eq!(client.find_successor(node.id()).await.unwrap(), node);
+
+    let unknown = GrpcClient::init(node.addr(), node.id().wrapping_add(1));
+    assert!(matches!(unknown.ping().await, Err(ClientError::Rejected(_))));
+
+    let closed = Node::new(closed_addr().await);
+    let result = closed.client::<GrpcClient>().ping().await;
+    assert!(matches!(result, Err(ClientError::ConnectionFailed(failed)) if failed == closed));
+}
@@ -0,0 +1,117 @@
+use std::net::SocketAddr;
+use async_trait::async_trait;
+use chord_rs::{Client, ClientError, Entry, MerkleTree, Node, Versioned};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::{Channel, Server};
+use crate::ChordService;
+use crate::proto::chord_client::ChordClient;
+
+mod client;
+mod server;
+
+/// Client of a node which is never reachable, the served nodes run alone in their ring
+pub(crate) struct OfflineClient {
+    node: Node,
+}
+
+impl OfflineClient {
+    fn fail<T>(&self) -> Result<T, ClientError> {
+        Err(ClientError::ConnectionFailed(self.node.clone()))
+    }
+}
+
+#[async_trait]
+impl Client for OfflineClient {
+    fn init(addr: SocketAddr, id: u64) -> Self {
+        Self { node: Node::with_id(id, addr) }
+    }
+
+    async fn find_successor(&self, _: u64) -> Result<Node, ClientError> {
+        self.fail()
+    }
+
+    async fn closest_preceding_node(&self, _: u64) -> Result<Node, ClientError> {
+        self.fail()
+    }
+
+    async fn successor(&self) -> Result<Node, ClientError> {
+        self.fail()
+    }
+
+    async fn successor_list(&self) -> Result<Vec<Node>, ClientError> {
+        self.fail()
+    }
+
+    async fn predecessor(&self) -> Result<Option<Node>, ClientError> {
+        self.fail()
+    }
+
+    async fn notify(&self, _: Node) -> Result<(), ClientError> {
+        self.fail()
+    }
+
+    async fn successor_leaving(&self, _: Node, _: Node) -> Result<(), ClientError> {
+        self.fail()
+    }
+
+    async fn predecessor_leaving(&self, _: Node, _: Option<Node>) -> Result<(), ClientError> {
+        self.fail()
+    }
+
+    async fn ping(&self) -> Result<(), ClientError> {
+        self.fail()
+    }
+
+    async fn put(&self, _: Vec<u8>, _: Versioned) -> Result<(), ClientError> {
+        self.fail()
+    }
+
+    async fn get(&self, _: Vec<u8>) -> Result<Vec<Versioned>, ClientError> {
+        self.fail()
+    }
+
+    async fn transfer_keys(&self, _: u64, _: u64, _: Option<Vec<u8>>) -> Result<Vec<Entry>, ClientError> {
+        self.fail()
+    }
+
+    async fn scan(&self, _: u64, _: u64, _: Option<Vec<u8>>, _: usize) -> Result<Vec<Entry>, ClientError> {
+        self.fail()
+    }
+
+    async fn confirm_transfer(&self, _: Vec<Entry>) -> Result<(), ClientError> {
+        self.fail()
+    }
+
+    async fn replicate(&self, _: Vec<Entry>) -> Result<(), ClientError> {
+        self.fail()
+    }
+
+    async fn merkle_tree(&self, _: u64, _: u64) -> Result<MerkleTree, ClientError> {
+        self.fail()
+    }
+}
+
+/// Serve the service on a free local port and get the address of the server
+pub(crate) async fn serve<C: Client + 'static>(service: ChordService<C>) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    serve_on(listener, service);
+
+    addr
+}
+
+/// Serve the service on the listener, e.g. when the node has to know its address up front
+pub(crate) fn serve_on<C: Client + 'static>(listener: TcpListener, service: ChordService<C>) {
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(service.into_server())
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+}
+
+pub(crate) async fn connect(addr: SocketAddr) -> ChordClient<Channel> {
+    ChordClient::connect(format!("http://{}", addr)).await.unwrap()
+}
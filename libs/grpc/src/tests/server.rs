@@ -0,0 +1,85 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use chord_rs::{KvStore, Node, NodeService};
+use tonic::Code;
+use crate::ChordService;
+use crate::proto;
+use crate::tests::{connect, serve, OfflineClient};
+
+fn node(id: u64) -> Node {
+    Node::with_id(id, SocketAddr::from(([127, 0, 0, 1], 42000 + id as u16)))
+}
+
+fn service() -> NodeService<OfflineClient> {
+    NodeService::new(SocketAddr::from(([127, 0, 0, 1], 42008)))
+}
+
+fn version(value: &[u8]) -> proto::Versioned {
+    proto::Versioned { value: Some(value.to_vec()), clock: [(8, 1)].into() }
+}
+
+#[tokio::test]
+async fn it_should_serve_chord_rpcs() {
+    let service = service();
+    let id = service.node().id();
+    let addr = serve(ChordService::new(service)).await;
+    let mut client = connect(addr).await;
+
+    let successor = client.find_successor(proto::IdRequest { node_id: id, id: 42 }).await.unwrap().into_inner();
+    assert_eq!(successor.node.unwrap().id, id);
+
+    client.ping(proto::NodeRequest { node_id: id }).await.unwrap();
+    let predecessor = client.predecessor(proto::NodeRequest { node_id: id }).await.unwrap().into_inner();
+    assert_eq!(predecessor.predecessor, None);
+
+    client.notify(proto::NotifyRequest { node_id: id, predecessor: Some(node(2).into()) }).await.unwrap();
+    let predecessor = client.predecessor(proto::NodeRequest { node_id: id }).await.unwrap().into_inner();
+    assert_eq!(predecessor.predecessor, Some(node(2).into()));
+
+    let successors = client.successor_list(proto::NodeRequest { node_id: id }).await.unwrap().into_inner();
+    assert_eq!(successors.nodes.len(), 1);
+}
+
+#[tokio::test]
+async fn it_should_serve_key_value_rpcs() {
+    let service = service();
+    let id = service.node().id();
+    let store = Arc::new(KvStore::new(service));
+    let addr = serve(ChordService::new(store.node().clone()).with_store(store.clone())).await;
+    let mut client = connect(addr).await;
+
+    let put = proto::PutRequest { node_id: id, key: b"key".to_vec(), version: Some(version(b"value")) };
+    client.put(put).await.unwrap();
+    assert_eq!(store.get_local(b"key").unwrap()[0].value.as_deref(), Some(b"value".as_slice()));
+
+    let versions = client.get(proto::KeyRequest { node_id: id, key: b"key".to_vec() }).await.unwrap().into_inner();
+    assert_eq!(versions.versions, vec![version(b"value")]);
+
+    let scan = proto::RangeRequest { node_id: id, from: 0, to: 0, after: None, limit: 10 };
+    let page = client.scan(scan).await.unwrap().into_inner();
+    assert_eq!(page.entries.len(), 1);
+
+    let tree = client.merkle_tree(proto::RangeRequest { node_id: id, from: 0, to: 0, after: None, limit: 0 }).await;
+    assert_eq!(tree.unwrap().into_inner().hashes, store.merkle_tree_local(0, 0).unwrap().hashes());
+
+    let tombstone = proto::Versioned { value: None, clock: [(8, 2)].into() };
+    client.put(proto::PutRequest { node_id: id, key: b"key".to_vec(), version: Some(tombstone) }).await.unwrap();
+    assert!(store.get_local(b"key").unwrap()[0].is_tombstone());
+}
+
+#[tokio::test]
+async fn it_should_reject_requests_it_cannot_serve() {
+    let service = service();
+    let id = service.node().id();
+    let addr = serve(ChordService::new(service)).await;
+    let mut client = connect(addr).await;
+
+    let unknown = client.ping(proto::NodeRequest { node_id: id.wrapping_add(1) }).await.unwrap_err();
+    assert_eq!(unknown.code(), Code::FailedPrecondition);
+
+    let no_store = client.get(proto::KeyRequest { node_id: id, key: b"key".to_vec() }).await.unwrap_err();
+    assert_eq!(no_store.code(), Code::FailedPrecondition);
+
+    let invalid = client.notify(proto::NotifyRequest { node_id: id, predecessor: None }).await.unwrap_err();
+    assert_eq!(invalid.code(), Code::InvalidArgument);
+}